@@ -1,7 +1,5 @@
-use std::collections::HashMap;
-
-use clap::{ArgAction, ArgMatches, Parser, Subcommand};
-use reedline_repl_rs::{CallBackMap, Repl, Result};
+use clap::{ArgAction, Parser, Subcommand};
+use reedline::{DefaultPrompt, DefaultPromptSegment, Reedline, Signal};
 
 #[derive(Parser, Debug)]
 #[command(name = "MyApp", version = "0.1.0", about = "My very cool app")]
@@ -31,31 +29,38 @@ pub enum SayCommands {
     },
 }
 
-fn say<T>(args: ArgMatches, _context: &mut T) -> Result<Option<String>> {
-    match args.subcommand() {
-        Some(("hello", args)) => Ok(Some(format!(
-            "Hello, {}!",
-            args.get_one::<String>("who").unwrap()
-        ))),
-        Some(("goodbye", args)) => {
-            let spanish = args.get_flag("spanish");
-            Ok(Some(format!(
-                "Goodbye, {}!",
-                if spanish { "adios" } else { "bye" }
-            )))
+fn say(command: SayCommands) -> String {
+    match command {
+        SayCommands::Hello { who, .. } => format!("Hello, {}!", who),
+        SayCommands::Goodbye { spanish } => {
+            format!("Goodbye, {}!", if spanish { "adios" } else { "bye" })
         }
-        _ => panic!("Unknown subcommand: {:?}", args.subcommand_name()),
     }
 }
 
-fn main() -> Result<()> {
-    let mut callbacks: CallBackMap<(), reedline_repl_rs::Error> = HashMap::new();
-
-    callbacks.insert("say".to_string(), say);
-
-    let mut repl = Repl::new(())
-        .with_banner("Welcome to MyApp")
-        .with_derived::<MyApp>(callbacks);
+fn main() -> anyhow::Result<()> {
+    println!("Welcome to MyApp");
+    let mut editor = Reedline::create();
+    let prompt = DefaultPrompt::new(
+        DefaultPromptSegment::Basic("MyApp".to_string()),
+        DefaultPromptSegment::Empty,
+    );
 
-    repl.run()
+    loop {
+        match editor.read_line(&prompt)? {
+            Signal::Success(line) if line.trim().is_empty() => {}
+            Signal::Success(line) => {
+                let args = std::iter::once("MyApp").chain(line.split_whitespace());
+                match MyApp::try_parse_from(args) {
+                    Ok(MyApp {
+                        command: Commands::Say { command },
+                    }) => println!("{}", say(command)),
+                    Err(e) => eprintln!("{}", e),
+                }
+            }
+            Signal::CtrlC => {}
+            Signal::CtrlD => break,
+        }
+    }
+    Ok(())
 }
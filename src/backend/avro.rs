@@ -0,0 +1,599 @@
+use std::{
+    fmt::{self, Write as _},
+    fs::File,
+    io::{Cursor, Seek, SeekFrom},
+    path::Path,
+};
+
+use arrow::{array::RecordBatch, datatypes::SchemaRef};
+use polars::export::arrow::io::avro::avro_schema::{
+    file::{CompressedBlock, FileMetadata},
+    read::{block_iterator, fallible_streaming_iterator::FallibleStreamingIterator, read_metadata},
+    schema::Schema,
+    write::{encode::zigzag_encode, write_block, write_metadata},
+};
+use polars::io::avro::AvroReader;
+use polars::prelude::*;
+
+use crate::{cli::FileOpts, BackendError};
+
+use super::{metadata::data_files, polars::to_record_batches};
+
+/// Read Avro object container files with polars, the files of a directory or glob are appended.
+pub fn read_avro(file_opts: &FileOpts) -> Result<DataFrame, BackendError> {
+    if !file_opts.partitions.is_empty() {
+        return Err(BackendError::Unsupported(
+            "partitioned avro files".to_string(),
+        ));
+    }
+
+    let mut frames = data_files(file_opts)?
+        .into_iter()
+        .map(|path| read_file(&path))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter();
+    let mut df = frames.next().unwrap_or_default();
+    for frame in frames {
+        df.vstack_mut(&frame)?;
+    }
+    Ok(df)
+}
+
+/// DataFusion has no avro reader without the `apache-avro` crate, the frame is converted instead.
+pub fn read_avro_batches(
+    file_opts: &FileOpts,
+) -> Result<(SchemaRef, Vec<RecordBatch>), BackendError> {
+    to_record_batches(read_avro(file_opts)?)
+}
+
+fn read_file(path: &Path) -> Result<DataFrame, BackendError> {
+    let invalid = |reason: String| BackendError::InvalidConnection {
+        conn: path.display().to_string(),
+        reason,
+    };
+    let mut file = File::open(path)?;
+    let metadata =
+        read_metadata(&mut file).map_err(|e| invalid(format!("not an avro file, {}", e)))?;
+    for field in &metadata.record.fields {
+        if !has_union(&field.schema) {
+            check_schema(&field.name, &field.schema)?;
+        }
+    }
+
+    if metadata.record.fields.iter().any(|f| has_union(&f.schema)) {
+        let container = unions_to_strings(file, &metadata)
+            .map_err(|e| invalid(format!("corrupt avro file, {}", e)))?;
+        return Ok(AvroReader::new(Cursor::new(container)).finish()?);
+    }
+    file.seek(SeekFrom::Start(0))?;
+    Ok(AvroReader::new(file).finish()?)
+}
+
+/// Polars reads nullable unions as nullable columns, maps have no column type.
+fn check_schema(column: &str, schema: &Schema) -> Result<(), BackendError> {
+    match schema {
+        Schema::Map(_) => Err(BackendError::Unsupported(format!(
+            "avro maps, found in column {}",
+            column
+        ))),
+        Schema::Union(schemas) => schemas.iter().try_for_each(|s| check_schema(column, s)),
+        Schema::Array(item) => check_schema(column, item),
+        Schema::Record(record) => record.fields.iter().try_for_each(|field| {
+            check_schema(&format!("{}.{}", column, field.name), &field.schema)
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Whether the schema holds a union of several types, not only a nullable type.
+fn has_union(schema: &Schema) -> bool {
+    match schema {
+        Schema::Union(schemas) => match schemas.as_slice() {
+            [Schema::Null, schema] | [schema, Schema::Null] => has_union(schema),
+            _ => true,
+        },
+        Schema::Array(item) | Schema::Map(item) => has_union(item),
+        Schema::Record(record) => record.fields.iter().any(|f| has_union(&f.schema)),
+        _ => false,
+    }
+}
+
+/// Polars has no column type for unions of several types, so the columns holding them are
+/// written as strings into a new uncompressed container, which is read instead.
+/// String and enum values are kept as they are, other values are written as JSON.
+fn unions_to_strings(mut file: File, metadata: &FileMetadata) -> Result<Vec<u8>, String> {
+    let fields = &metadata.record.fields;
+    let unions = fields
+        .iter()
+        .map(|f| has_union(&f.schema))
+        .collect::<Vec<_>>();
+    let mut record = metadata.record.clone();
+    for (field, _) in record.fields.iter_mut().zip(&unions).filter(|(_, u)| **u) {
+        field.schema = Schema::Union(vec![Schema::Null, Schema::String(None)]);
+        field.default = None;
+    }
+
+    let mut container = vec![];
+    write_metadata(&mut container, record, None).map_err(|e| e.to_string())?;
+    let mut blocks = block_iterator(&mut file, metadata.compression, metadata.marker);
+    while let Some(block) = blocks.next().map_err(|e| e.to_string())? {
+        let mut data = block.data.as_slice();
+        let mut rows = vec![];
+        for _ in 0..block.number_of_rows {
+            for (field, union) in fields.iter().zip(&unions) {
+                if *union {
+                    match column_value(&field.schema, &mut data)? {
+                        Some(value) => {
+                            zigzag_encode(1, &mut rows).map_err(|e| e.to_string())?;
+                            zigzag_encode(value.len() as i64, &mut rows)
+                                .map_err(|e| e.to_string())?;
+                            rows.extend_from_slice(value.as_bytes());
+                        }
+                        None => zigzag_encode(0, &mut rows).map_err(|e| e.to_string())?,
+                    }
+                } else {
+                    // the other columns are copied as they are
+                    let start = data;
+                    decode(&field.schema, &mut data, None)?;
+                    rows.extend_from_slice(&start[..start.len() - data.len()]);
+                }
+            }
+        }
+        let block = CompressedBlock::new(block.number_of_rows, rows);
+        write_block(&mut container, &block).map_err(|e| e.to_string())?;
+    }
+    Ok(container)
+}
+
+/// The value of a union column as a string, null for null values.
+fn column_value(schema: &Schema, data: &mut &[u8]) -> Result<Option<String>, String> {
+    Ok(match schema {
+        Schema::Null => None,
+        Schema::Union(schemas) => return column_value(branch(schemas, data)?, data),
+        Schema::String(_) => Some(String::from_utf8_lossy(take_bytes(data)?).into_owned()),
+        Schema::Enum(e) => Some(symbol(&e.symbols, data)?.to_string()),
+        Schema::Float | Schema::Double => {
+            let mut json = String::new();
+            decode(schema, data, Some(&mut json))?;
+            // NaN and infinities are unquoted like the other strings
+            Some(json.trim_matches('"').to_string())
+        }
+        schema => {
+            let mut json = String::new();
+            decode(schema, data, Some(&mut json))?;
+            Some(json)
+        }
+    })
+}
+
+/// Decode the value of `schema` at the start of `data`, written as JSON to `out` if given.
+fn decode(schema: &Schema, data: &mut &[u8], mut out: Option<&mut String>) -> Result<(), String> {
+    match schema {
+        Schema::Null => push(&mut out, "null"),
+        Schema::Boolean => push(&mut out, take(data, 1)?[0] != 0),
+        Schema::Int(_) | Schema::Long(_) => push(&mut out, read_long(data)?),
+        Schema::Float => push(
+            &mut out,
+            JsonFloat(f32::from_le_bytes(
+                take(data, 4)?.try_into().expect("4 bytes"),
+            )),
+        ),
+        Schema::Double => push(
+            &mut out,
+            JsonFloat(f64::from_le_bytes(
+                take(data, 8)?.try_into().expect("8 bytes"),
+            )),
+        ),
+        Schema::Bytes(_) => push(&mut out, Json(&hex(take_bytes(data)?))),
+        Schema::Fixed(fixed) => push(&mut out, Json(&hex(take(data, fixed.size)?))),
+        Schema::String(_) => push(&mut out, Json(&String::from_utf8_lossy(take_bytes(data)?))),
+        Schema::Enum(e) => push(&mut out, Json(symbol(&e.symbols, data)?)),
+        Schema::Union(schemas) => return decode(branch(schemas, data)?, data, out),
+        Schema::Record(record) => {
+            push(&mut out, '{');
+            for (i, field) in record.fields.iter().enumerate() {
+                if i > 0 {
+                    push(&mut out, ',');
+                }
+                push(&mut out, format_args!("{}:", Json(&field.name)));
+                decode(&field.schema, data, out.as_deref_mut())?;
+            }
+            push(&mut out, '}');
+        }
+        Schema::Array(item) => {
+            push(&mut out, '[');
+            let mut first = true;
+            while let Some(count) = block_count(data)? {
+                for _ in 0..count {
+                    if !std::mem::take(&mut first) {
+                        push(&mut out, ',');
+                    }
+                    decode(item, data, out.as_deref_mut())?;
+                }
+            }
+            push(&mut out, ']');
+        }
+        Schema::Map(values) => {
+            push(&mut out, '{');
+            let mut first = true;
+            while let Some(count) = block_count(data)? {
+                for _ in 0..count {
+                    if !std::mem::take(&mut first) {
+                        push(&mut out, ',');
+                    }
+                    let key = String::from_utf8_lossy(take_bytes(data)?);
+                    push(&mut out, format_args!("{}:", Json(&key)));
+                    decode(values, data, out.as_deref_mut())?;
+                }
+            }
+            push(&mut out, '}');
+        }
+    }
+    Ok(())
+}
+
+fn push(out: &mut Option<&mut String>, value: impl fmt::Display) {
+    if let Some(out) = out {
+        let _ = write!(out, "{}", value);
+    }
+}
+
+/// A string written as a JSON string.
+struct Json<'a>(&'a str);
+
+impl fmt::Display for Json<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_char('"')?;
+        for c in self.0.chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                '\r' => f.write_str("\\r")?,
+                '\t' => f.write_str("\\t")?,
+                c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+                c => f.write_char(c)?,
+            }
+        }
+        f.write_char('"')
+    }
+}
+
+/// A float written as a JSON number. JSON has no number for NaN and infinities,
+/// they are written as the strings `"NaN"`, `"Infinity"` and `"-Infinity"`.
+struct JsonFloat<T>(T);
+
+impl<T: fmt::Display + Copy + Into<f64>> fmt::Display for JsonFloat<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value: f64 = self.0.into();
+        if value.is_nan() {
+            write!(f, "{}", Json("NaN"))
+        } else if value.is_infinite() {
+            let name = if value > 0.0 { "Infinity" } else { "-Infinity" };
+            write!(f, "{}", Json(name))
+        } else {
+            write!(f, "{}", self.0)
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn read_long(data: &mut &[u8]) -> Result<i64, String> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = take(data, 1)?[0];
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok((value >> 1) as i64 ^ -((value & 1) as i64));
+        }
+    }
+    Err("invalid variable length integer".to_string())
+}
+
+fn take<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8], String> {
+    if data.len() < len {
+        return Err("unexpected end of block".to_string());
+    }
+    let (value, rest) = data.split_at(len);
+    *data = rest;
+    Ok(value)
+}
+
+fn take_bytes<'a>(data: &mut &'a [u8]) -> Result<&'a [u8], String> {
+    let len = read_long(data)?;
+    let len = usize::try_from(len).map_err(|_| format!("invalid length {}", len))?;
+    take(data, len)
+}
+
+fn branch<'a>(schemas: &'a [Schema], data: &mut &[u8]) -> Result<&'a Schema, String> {
+    let index = read_long(data)?;
+    usize::try_from(index)
+        .ok()
+        .and_then(|i| schemas.get(i))
+        .ok_or_else(|| format!("invalid union branch {}", index))
+}
+
+fn symbol<'a>(symbols: &'a [String], data: &mut &[u8]) -> Result<&'a str, String> {
+    let index = read_long(data)?;
+    usize::try_from(index)
+        .ok()
+        .and_then(|i| symbols.get(i))
+        .map(String::as_str)
+        .ok_or_else(|| format!("invalid enum symbol {}", index))
+}
+
+/// The number of items of the next block of an array or map, none after the last block.
+fn block_count(data: &mut &[u8]) -> Result<Option<u64>, String> {
+    match read_long(data)? {
+        0 => Ok(None),
+        count if count < 0 => {
+            // negative counts are followed by the size of the block in bytes
+            read_long(data)?;
+            Ok(Some(count.unsigned_abs()))
+        }
+        count => Ok(Some(count as u64)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use arrow::{
+        array::{Array, AsArray},
+        datatypes::{DataType, Date32Type, Decimal128Type, Float64Type, Int64Type, TimeUnit},
+    };
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::cli::DatasetConn;
+
+    const SCHEMA: &str = r#"{
+        "type": "record",
+        "name": "event",
+        "fields": [
+            {"name": "id", "type": "long"},
+            {"name": "kind", "type": {"type": "enum", "name": "kind", "symbols": ["click", "view"]}},
+            {"name": "score", "type": ["null", "double"]},
+            {"name": "at", "type": {"type": "long", "logicalType": "timestamp-millis"}},
+            {"name": "day", "type": {"type": "int", "logicalType": "date"}},
+            {"name": "amount", "type": {"type": "bytes", "logicalType": "decimal", "precision": 10, "scale": 2}},
+            {"name": "tags", "type": {"type": "array", "items": "string"}}
+        ]
+    }"#;
+
+    fn long(buf: &mut Vec<u8>, n: i64) {
+        let mut n = ((n << 1) ^ (n >> 63)) as u64;
+        while n >= 0x80 {
+            buf.push((n as u8) | 0x80);
+            n >>= 7;
+        }
+        buf.push(n as u8);
+    }
+
+    fn bytes(buf: &mut Vec<u8>, b: &[u8]) {
+        long(buf, b.len() as i64);
+        buf.extend_from_slice(b);
+    }
+
+    /// An object container file of a single uncompressed block.
+    fn container(schema: &str, rows: usize, data: &[u8]) -> Vec<u8> {
+        let marker = [7u8; 16];
+        let mut buf = b"Obj\x01".to_vec();
+        long(&mut buf, 2);
+        bytes(&mut buf, b"avro.schema");
+        bytes(&mut buf, schema.as_bytes());
+        bytes(&mut buf, b"avro.codec");
+        bytes(&mut buf, b"null");
+        long(&mut buf, 0);
+        buf.extend_from_slice(&marker);
+        long(&mut buf, rows as i64);
+        bytes(&mut buf, data);
+        buf.extend_from_slice(&marker);
+        buf
+    }
+
+    fn events() -> Vec<u8> {
+        let mut data = vec![];
+        // id, kind, score, at, day, amount, tags
+        long(&mut data, 1);
+        long(&mut data, 1);
+        long(&mut data, 1);
+        data.extend_from_slice(&2.5f64.to_le_bytes());
+        long(&mut data, 1_724_889_600_000);
+        long(&mut data, 19_964);
+        bytes(&mut data, &12_345i16.to_be_bytes());
+        long(&mut data, 2);
+        bytes(&mut data, b"a");
+        bytes(&mut data, b"b");
+        long(&mut data, 0);
+
+        long(&mut data, 2);
+        long(&mut data, 0);
+        long(&mut data, 0);
+        long(&mut data, 1_724_976_000_000);
+        long(&mut data, 19_965);
+        bytes(&mut data, &(-5i8).to_be_bytes());
+        long(&mut data, 0);
+        container(SCHEMA, 2, &data)
+    }
+
+    fn file_opts(dir: &TempDir, files: &[(&str, Vec<u8>)]) -> FileOpts {
+        for (name, content) in files {
+            fs::write(dir.path().join(name), content).unwrap();
+        }
+        let conn = if files.len() == 1 {
+            dir.path().join(files[0].0)
+        } else {
+            dir.path().to_path_buf()
+        };
+        match DatasetConn::resolve(conn.to_str().unwrap(), None).unwrap() {
+            DatasetConn::Avro(file_opts) => file_opts,
+            other => panic!("expect an avro dataset, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn avro_types_should_map_to_arrow() {
+        let dir = TempDir::new().unwrap();
+        let (schema, batches) =
+            read_avro_batches(&file_opts(&dir, &[("events.avro", events())])).unwrap();
+
+        let types = schema
+            .fields()
+            .iter()
+            .map(|f| (f.name().as_str(), f.data_type().clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(types[0], ("id", DataType::Int64));
+        assert!(matches!(types[1].1, DataType::Dictionary(..)));
+        assert_eq!(types[2], ("score", DataType::Float64));
+        assert!(matches!(
+            types[3].1,
+            DataType::Timestamp(TimeUnit::Millisecond, Some(_))
+        ));
+        assert_eq!(types[4], ("day", DataType::Date32));
+        assert_eq!(types[5], ("amount", DataType::Decimal128(10, 2)));
+        assert!(matches!(types[6].1, DataType::LargeList(_)));
+
+        let batch = arrow::compute::concat_batches(&schema, &batches).unwrap();
+        assert_eq!(batch.num_rows(), 2);
+        let ids = batch.column(0).as_primitive::<Int64Type>();
+        assert_eq!(ids.values(), &[1, 2]);
+        let kinds = arrow::compute::cast(batch.column(1), &DataType::Utf8).unwrap();
+        let kinds = kinds.as_string::<i32>();
+        assert_eq!((kinds.value(0), kinds.value(1)), ("view", "click"));
+        let scores = batch.column(2).as_primitive::<Float64Type>();
+        assert_eq!(scores.value(0), 2.5);
+        assert!(scores.is_null(1));
+        let days = batch.column(4).as_primitive::<Date32Type>();
+        assert_eq!(days.values(), &[19_964, 19_965]);
+        let amounts = batch.column(5).as_primitive::<Decimal128Type>();
+        assert_eq!(amounts.values(), &[12_345, -5]);
+        let tags = batch.column(6).as_list::<i64>();
+        assert_eq!((tags.value_length(0), tags.value_length(1)), (2, 0));
+    }
+
+    #[test]
+    fn avro_files_of_a_directory_should_be_appended() {
+        let dir = TempDir::new().unwrap();
+        let files = [("a.avro", events()), ("b.avro", events())];
+        let df = read_avro(&file_opts(&dir, &files)).unwrap();
+        assert_eq!(df.height(), 4);
+    }
+
+    #[test]
+    fn avro_files_of_other_formats_should_be_skipped() {
+        let dir = TempDir::new().unwrap();
+        let files = [
+            ("a.avro", events()),
+            ("_SUCCESS", vec![]),
+            ("notes.txt", b"not avro".to_vec()),
+        ];
+        let df = read_avro(&file_opts(&dir, &files)).unwrap();
+        assert_eq!(df.height(), 2);
+    }
+
+    #[test]
+    fn avro_unions_of_several_types_should_be_read_as_strings() {
+        let schema = r#"{"type": "record", "name": "r", "fields": [
+            {"name": "value", "type": ["null", "long", "string",
+                {"type": "record", "name": "point", "fields": [
+                    {"name": "x", "type": "double"},
+                    {"name": "tags", "type": {"type": "array", "items": "string"}}
+                ]}
+            ]},
+            {"name": "id", "type": "long"}
+        ]}"#;
+        let mut data = vec![];
+        // value, id
+        long(&mut data, 1);
+        long(&mut data, 7);
+        long(&mut data, 1);
+
+        long(&mut data, 2);
+        bytes(&mut data, b"a\"b");
+        long(&mut data, 2);
+
+        long(&mut data, 0);
+        long(&mut data, 3);
+
+        long(&mut data, 3);
+        data.extend_from_slice(&1.5f64.to_le_bytes());
+        long(&mut data, 2);
+        bytes(&mut data, b"x");
+        bytes(&mut data, b"y");
+        long(&mut data, 0);
+        long(&mut data, 4);
+        let dir = TempDir::new().unwrap();
+        let file = file_opts(&dir, &[("r.avro", container(schema, 4, &data))]);
+
+        let df = read_avro(&file).unwrap();
+        let values = df.column("value").unwrap().str().unwrap();
+        assert_eq!(
+            values.into_iter().collect::<Vec<_>>(),
+            [
+                Some("7"),
+                Some("a\"b"),
+                None,
+                Some(r#"{"x":1.5,"tags":["x","y"]}"#)
+            ]
+        );
+        let ids = df.column("id").unwrap().i64().unwrap();
+        assert_eq!(
+            ids.into_iter().collect::<Vec<_>>(),
+            [Some(1), Some(2), Some(3), Some(4)]
+        );
+    }
+
+    #[test]
+    fn avro_non_finite_floats_should_be_read_as_strings() {
+        let schema = r#"{"type": "record", "name": "r", "fields": [
+            {"name": "value", "type": ["double", "string",
+                {"type": "record", "name": "range", "fields": [
+                    {"name": "low", "type": "float"},
+                    {"name": "high", "type": "double"}
+                ]}
+            ]}
+        ]}"#;
+        let mut data = vec![];
+        long(&mut data, 0);
+        data.extend_from_slice(&f64::NAN.to_le_bytes());
+        long(&mut data, 0);
+        data.extend_from_slice(&0.5f64.to_le_bytes());
+        long(&mut data, 2);
+        data.extend_from_slice(&f32::NEG_INFINITY.to_le_bytes());
+        data.extend_from_slice(&f64::INFINITY.to_le_bytes());
+        let dir = TempDir::new().unwrap();
+        let file = file_opts(&dir, &[("r.avro", container(schema, 3, &data))]);
+
+        let df = read_avro(&file).unwrap();
+        let values = df.column("value").unwrap().str().unwrap();
+        assert_eq!(
+            values.into_iter().collect::<Vec<_>>(),
+            [
+                Some("NaN"),
+                Some("0.5"),
+                Some(r#"{"low":"-Infinity","high":"Infinity"}"#)
+            ]
+        );
+    }
+
+    #[test]
+    fn avro_maps_should_be_rejected() {
+        let schema = r#"{"type": "record", "name": "r", "fields": [
+            {"name": "inner", "type": {"type": "record", "name": "i", "fields": [
+                {"name": "attrs", "type": {"type": "map", "values": "string"}}
+            ]}}
+        ]}"#;
+        let mut data = vec![];
+        long(&mut data, 0);
+        let dir = TempDir::new().unwrap();
+        let file = file_opts(&dir, &[("r.avro", container(schema, 1, &data))]);
+
+        let err = read_avro(&file).unwrap_err().to_string();
+        assert_eq!(err, "Not supported: avro maps, found in column inner.attrs");
+    }
+}
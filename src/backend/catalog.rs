@@ -0,0 +1,17 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, RwLock},
+};
+
+/// The names known to a backend, used to complete the input at the prompt.
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    /// The column names of every registered dataset.
+    pub datasets: BTreeMap<String, Vec<String>>,
+    /// The SQL functions the engine provides.
+    pub functions: Vec<String>,
+}
+
+/// The catalog shared between the backend thread, which refreshes it after
+/// every command, and the repl.
+pub type SharedCatalog = Arc<RwLock<Catalog>>;
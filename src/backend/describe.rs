@@ -0,0 +1,89 @@
+use crate::{cli::OutputFormat, BackendError, ReplDisplay};
+
+/// The result of `describe`, in two tables as the statistics do not share a type: the
+/// counts and moments of every column as floats, then the values of the columns like min,
+/// max and top, in the type of their column.
+pub struct Described<T> {
+    pub statistics: Option<T>,
+    pub values: Option<T>,
+}
+
+impl<T: ReplDisplay> ReplDisplay for Described<T> {
+    async fn display(self, format: OutputFormat) -> Result<String, BackendError> {
+        let mut tables = vec![];
+        for table in [self.statistics, self.values].into_iter().flatten() {
+            tables.push(table.display(format).await?);
+        }
+        Ok(tables.join("\n\n"))
+    }
+}
+
+/// The statistics computed by `describe`, in output order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DescribeMethod {
+    Count,
+    NullCount,
+    Mean,
+    Std,
+    Min,
+    Max,
+    Median,
+    /// A percentile, from 1 to 99.
+    Percentile(u8),
+    ApproxDistinct,
+    Distinct,
+    /// The most frequent value, the smallest one on a tie.
+    Top,
+    /// How many times the most frequent value occurs.
+    TopCount,
+    MinLength,
+    AvgLength,
+    MaxLength,
+    TrueCount,
+    FalseCount,
+}
+
+impl DescribeMethod {
+    /// The statistics computed unless more are asked for.
+    pub const DEFAULT: [DescribeMethod; 7] = [
+        Self::Count,
+        Self::NullCount,
+        Self::Mean,
+        Self::Std,
+        Self::Min,
+        Self::Max,
+        Self::Median,
+    ];
+
+    pub fn name(&self) -> String {
+        match self {
+            Self::Count => "count".to_string(),
+            Self::NullCount => "null_count".to_string(),
+            Self::Mean => "mean".to_string(),
+            Self::Std => "std".to_string(),
+            Self::Min => "min".to_string(),
+            Self::Max => "max".to_string(),
+            Self::Median => "median".to_string(),
+            Self::Percentile(p) => format!("p{}", p),
+            Self::ApproxDistinct => "approx_distinct".to_string(),
+            Self::Distinct => "distinct".to_string(),
+            Self::Top => "top".to_string(),
+            Self::TopCount => "top_count".to_string(),
+            Self::MinLength => "min_length".to_string(),
+            Self::AvgLength => "avg_length".to_string(),
+            Self::MaxLength => "max_length".to_string(),
+            Self::TrueCount => "true_count".to_string(),
+            Self::FalseCount => "false_count".to_string(),
+        }
+    }
+
+    /// The alias of this statistic for the column at `index` in the aggregate result.
+    pub fn alias(&self, index: usize) -> String {
+        format!("{}_{}", self.name(), index)
+    }
+
+    /// Whether the statistic is a value of the column, kept in the type of the column.
+    pub fn is_value(&self) -> bool {
+        matches!(self, Self::Min | Self::Max | Self::Top)
+    }
+}
@@ -0,0 +1,384 @@
+use std::sync::Arc;
+
+use arrow::{
+    array::{ArrayRef, RecordBatch, StringArray},
+    compute::{cast_with_options, CastOptions},
+    datatypes::{DataType, Field, Schema, TimeUnit},
+};
+use calamine::{open_workbook_auto, Data, Range, Reader};
+
+use crate::{
+    cli::{FileOpts, ReadOpts},
+    BackendError,
+};
+
+/// Read the sheet given with `--sheet`, or the only sheet of the workbook. Workbooks of
+/// several sheets need `--sheet`, so both engines register the same table.
+pub fn read_workbook(file_opts: &FileOpts, read: &ReadOpts) -> Result<RecordBatch, BackendError> {
+    let path = file_opts.single_file("excel files")?;
+    let mut workbook = open_workbook_auto(path)?;
+    let names = workbook.sheet_names();
+    let name = match &read.sheet {
+        None if names.len() == 1 => names[0].clone(),
+        None => {
+            return Err(BackendError::InvalidConnection {
+                conn: path.to_string(),
+                reason: format!(
+                    "the workbook has {} sheets ({}), choose one with --sheet",
+                    names.len(),
+                    names.join(", ")
+                ),
+            })
+        }
+        Some(sheet) if names.contains(sheet) => sheet.clone(),
+        // a position is only tried when no sheet has this name
+        Some(sheet) => match sheet.parse::<usize>() {
+            Ok(n) if (1..=names.len()).contains(&n) => names[n - 1].clone(),
+            _ => return Err(BackendError::SheetNotFound(sheet.clone())),
+        },
+    };
+
+    let range = workbook.worksheet_range(&name)?;
+    read_sheet(&name, &range, read)
+}
+
+fn read_sheet(
+    sheet: &str,
+    range: &Range<Data>,
+    read: &ReadOpts,
+) -> Result<RecordBatch, BackendError> {
+    let range = match &read.range {
+        Some(cells) => range.range(cells.start, cells.end),
+        None => range.clone(),
+    };
+    let Some((first_row, _)) = range.start() else {
+        return Ok(RecordBatch::new_empty(Arc::new(Schema::empty())));
+    };
+
+    // the header is given as a row number of the sheet, the range is counted from its start
+    let header = match (read.no_header, read.header_row) {
+        (true, _) => None,
+        (false, None) => Some(0),
+        (false, Some(row)) => match (row as usize).checked_sub(first_row as usize + 1) {
+            Some(row) if row < range.height() => Some(row),
+            _ => {
+                return Err(BackendError::InvalidSheet {
+                    sheet: sheet.to_string(),
+                    reason: format!("header row {} is out of the cells read", row),
+                })
+            }
+        },
+    };
+    let rows = range.rows().collect::<Vec<_>>();
+    let data = &rows[header.map_or(0, |row| row + 1)..];
+
+    let mut names = (0..range.width())
+        .map(|i| {
+            header
+                .and_then(|row| cell_value(&rows[row][i]))
+                .unwrap_or_else(|| format!("column_{}", i + 1))
+        })
+        .collect::<Vec<_>>();
+    dedup_names(&mut names);
+    let columns = match &read.schema {
+        Some(schema) if schema.columns.len() != names.len() => {
+            return Err(BackendError::InvalidSheet {
+                sheet: sheet.to_string(),
+                reason: format!(
+                    "--schema has {} columns, the sheet has {}",
+                    schema.columns.len(),
+                    names.len()
+                ),
+            })
+        }
+        Some(schema) => schema.columns.clone(),
+        None => names
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| (name, infer_type(data, i, read.infer_rows)))
+            .collect(),
+    };
+
+    // cells are read as strings and cast to the column type, so bad cells fail like csv values
+    let options = CastOptions {
+        safe: false,
+        ..Default::default()
+    };
+    let mut fields = vec![];
+    let mut arrays = vec![];
+    for (i, (name, data_type)) in columns.into_iter().enumerate() {
+        let values = data
+            .iter()
+            .map(|row| cell_value(&row[i]))
+            .collect::<StringArray>();
+        let array: ArrayRef = cast_with_options(&values, &data_type, &options).map_err(|e| {
+            BackendError::InvalidSheet {
+                sheet: sheet.to_string(),
+                reason: format!("column {}, {}", name, e),
+            }
+        })?;
+        fields.push(Field::new(name, data_type, true));
+        arrays.push(array);
+    }
+    Ok(RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays)?)
+}
+
+/// The kind of values a cell holds, widened over the cells of a column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CellKind {
+    Int,
+    Float,
+    Bool,
+    Date,
+    Timestamp,
+    Text,
+}
+
+fn infer_type(rows: &[&[Data]], column: usize, limit: Option<usize>) -> DataType {
+    let kind = rows
+        .iter()
+        .take(limit.unwrap_or(usize::MAX))
+        .filter_map(|row| cell_kind(&row[column]))
+        .reduce(|a, b| match (a, b) {
+            (a, b) if a == b => a,
+            (CellKind::Int | CellKind::Float, CellKind::Int | CellKind::Float) => CellKind::Float,
+            (CellKind::Date | CellKind::Timestamp, CellKind::Date | CellKind::Timestamp) => {
+                CellKind::Timestamp
+            }
+            _ => CellKind::Text,
+        });
+    match kind {
+        Some(CellKind::Int) => DataType::Int64,
+        Some(CellKind::Float) => DataType::Float64,
+        Some(CellKind::Bool) => DataType::Boolean,
+        Some(CellKind::Date) => DataType::Date32,
+        Some(CellKind::Timestamp) => DataType::Timestamp(TimeUnit::Millisecond, None),
+        Some(CellKind::Text) | None => DataType::Utf8,
+    }
+}
+
+fn cell_kind(cell: &Data) -> Option<CellKind> {
+    match cell {
+        Data::Int(_) => Some(CellKind::Int),
+        // excel stores every number as a float
+        Data::Float(f) if is_integral(*f) => Some(CellKind::Int),
+        Data::Float(_) => Some(CellKind::Float),
+        Data::Bool(_) => Some(CellKind::Bool),
+        Data::DateTime(dt) => match dt.as_datetime() {
+            Some(dt) if dt.time() == chrono::NaiveTime::MIN => Some(CellKind::Date),
+            Some(_) => Some(CellKind::Timestamp),
+            None => Some(CellKind::Float),
+        },
+        Data::DateTimeIso(s) if s.len() == 10 => Some(CellKind::Date),
+        Data::DateTimeIso(_) => Some(CellKind::Timestamp),
+        Data::String(_) | Data::DurationIso(_) => Some(CellKind::Text),
+        Data::Error(_) | Data::Empty => None,
+    }
+}
+
+/// The text of a cell, which the arrow casts parse into the column type.
+fn cell_value(cell: &Data) -> Option<String> {
+    match cell {
+        Data::Int(i) => Some(i.to_string()),
+        Data::Float(f) if is_integral(*f) => Some((*f as i64).to_string()),
+        Data::Float(f) => Some(f.to_string()),
+        Data::Bool(b) => Some(b.to_string()),
+        Data::DateTime(dt) => match dt.as_datetime() {
+            Some(dt) if dt.time() == chrono::NaiveTime::MIN => {
+                Some(dt.format("%Y-%m-%d").to_string())
+            }
+            Some(dt) => Some(dt.format("%Y-%m-%dT%H:%M:%S%.3f").to_string()),
+            None => Some(dt.as_f64().to_string()),
+        },
+        Data::String(s) | Data::DateTimeIso(s) | Data::DurationIso(s) => Some(s.clone()),
+        Data::Error(_) | Data::Empty => None,
+    }
+}
+
+fn is_integral(f: f64) -> bool {
+    f.fract() == 0.0 && f.abs() < (1u64 << 53) as f64
+}
+
+/// Header cells may repeat, the later ones get a `_2`, `_3`, ... suffix.
+fn dedup_names(names: &mut [String]) {
+    for i in 1..names.len() {
+        let mut n = 1;
+        let name = names[i].clone();
+        while names[..i].contains(&names[i]) {
+            n += 1;
+            names[i] = format!("{}_{}", name, n);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::util::pretty::pretty_format_batches;
+    use calamine::{ExcelDateTime, ExcelDateTimeType};
+
+    use super::*;
+
+    fn date_time(value: f64) -> Data {
+        Data::DateTime(ExcelDateTime::new(
+            value,
+            ExcelDateTimeType::DateTime,
+            false,
+        ))
+    }
+
+    fn sales() -> Range<Data> {
+        let mut workbook = open_workbook_auto("fixtures/sales.xlsx").unwrap();
+        workbook.worksheet_range("Q1 Sales").unwrap()
+    }
+
+    fn types(batch: &RecordBatch) -> Vec<(String, DataType)> {
+        batch
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| (f.name().clone(), f.data_type().clone()))
+            .collect()
+    }
+
+    #[test]
+    fn infer_type_should_widen_over_the_column() {
+        let rows = [
+            vec![
+                Data::Float(1.0),
+                Data::Int(1),
+                Data::Bool(true),
+                date_time(45533.0),
+            ],
+            vec![
+                Data::Empty,
+                Data::Float(2.5),
+                Data::Empty,
+                date_time(45533.5),
+            ],
+            vec![
+                Data::Int(3),
+                Data::Int(4),
+                Data::String("x".into()),
+                Data::Empty,
+            ],
+        ];
+        let rows = rows.iter().map(Vec::as_slice).collect::<Vec<_>>();
+        assert_eq!(infer_type(&rows, 0, None), DataType::Int64);
+        assert_eq!(infer_type(&rows, 1, None), DataType::Float64);
+        assert_eq!(infer_type(&rows, 2, None), DataType::Utf8);
+        assert_eq!(infer_type(&rows, 2, Some(2)), DataType::Boolean);
+        assert_eq!(infer_type(&rows, 3, Some(1)), DataType::Date32);
+        assert_eq!(
+            infer_type(&rows, 3, None),
+            DataType::Timestamp(TimeUnit::Millisecond, None)
+        );
+        assert_eq!(infer_type(&rows[2..], 3, None), DataType::Utf8);
+    }
+
+    #[test]
+    fn cell_value_should_be_parsed_by_the_casts() {
+        assert_eq!(cell_value(&Data::Float(3.0)).as_deref(), Some("3"));
+        assert_eq!(cell_value(&Data::Float(2.5)).as_deref(), Some("2.5"));
+        assert_eq!(
+            cell_value(&Data::Float(1e300)).as_deref(),
+            Some(&*1e300f64.to_string())
+        );
+        assert_eq!(cell_value(&Data::Bool(false)).as_deref(), Some("false"));
+        assert_eq!(
+            cell_value(&date_time(45533.0)).as_deref(),
+            Some("2024-08-29")
+        );
+        assert_eq!(
+            cell_value(&date_time(45533.5)).as_deref(),
+            Some("2024-08-29T12:00:00.000")
+        );
+        assert_eq!(cell_value(&Data::Empty), None);
+        assert_eq!(
+            cell_value(&Data::Error(calamine::CellErrorType::Div0)),
+            None
+        );
+    }
+
+    #[test]
+    fn read_sheet_should_use_the_header_row() {
+        let read = ReadOpts {
+            header_row: Some(2),
+            ..Default::default()
+        };
+        let batch = read_sheet("Q1 Sales", &sales(), &read).unwrap();
+        assert_eq!(
+            types(&batch),
+            [
+                ("region".to_string(), DataType::Utf8),
+                ("units".to_string(), DataType::Int64),
+                ("price".to_string(), DataType::Float64),
+                ("active".to_string(), DataType::Boolean),
+                (
+                    "day".to_string(),
+                    DataType::Timestamp(TimeUnit::Millisecond, None)
+                ),
+                ("region_2".to_string(), DataType::Utf8),
+            ]
+        );
+        assert_eq!(
+            pretty_format_batches(&[batch]).unwrap().to_string(),
+            "\
++--------+-------+-------+--------+---------------------+----------+
+| region | units | price | active | day                 | region_2 |
++--------+-------+-------+--------+---------------------+----------+
+| eu     | 3     | 2.5   | true   | 2024-08-29T00:00:00 | x        |
+| us     | 4     | 1.25  | false  | 2024-08-30T00:00:00 | y        |
+| apac   |       | 3.0   | true   | 2024-08-31T12:00:00 | z        |
++--------+-------+-------+--------+---------------------+----------+"
+        );
+
+        let read = ReadOpts {
+            header_row: Some(6),
+            ..Default::default()
+        };
+        let err = read_sheet("Q1 Sales", &sales(), &read).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            BackendError::InvalidSheet {
+                sheet: "Q1 Sales".to_string(),
+                reason: "header row 6 is out of the cells read".to_string(),
+            }
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn read_sheet_should_read_the_range_only() {
+        let read = ReadOpts {
+            range: Some("B3:D4".parse().unwrap()),
+            no_header: true,
+            ..Default::default()
+        };
+        let batch = read_sheet("Q1 Sales", &sales(), &read).unwrap();
+        assert_eq!(
+            types(&batch),
+            [
+                ("column_1".to_string(), DataType::Int64),
+                ("column_2".to_string(), DataType::Float64),
+                ("column_3".to_string(), DataType::Boolean),
+            ]
+        );
+        assert_eq!(batch.num_rows(), 2);
+
+        // the header row is a row of the sheet, not of the range
+        let read = ReadOpts {
+            range: Some("A2:B3".parse().unwrap()),
+            header_row: Some(2),
+            ..Default::default()
+        };
+        let batch = read_sheet("Q1 Sales", &sales(), &read).unwrap();
+        assert_eq!(
+            types(&batch),
+            [
+                ("region".to_string(), DataType::Utf8),
+                ("units".to_string(), DataType::Int64),
+            ]
+        );
+        assert_eq!(batch.num_rows(), 1);
+    }
+}
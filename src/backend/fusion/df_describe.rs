@@ -1,219 +1,435 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
-use arrow::array::{ArrayRef, RecordBatch, StringArray};
-use arrow::compute::{cast, concat};
-use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
-use arrow_cast::pretty::pretty_format_batches;
-use datafusion::logical_expr::col;
+use arrow::array::{new_null_array, ArrayRef, RecordBatch, StringArray};
+use arrow::compute::{concat, concat_batches};
+use arrow::datatypes::{DataType, Field, Schema};
+use datafusion::error::Result as DFResult;
+use datafusion::functions::expr_fn::character_length;
 use datafusion::prelude::DataFrame;
-use datafusion_expr::{case, is_null, lit, max, min};
-use datafusion_functions_aggregate::expr_fn::{avg, count, median, stddev, sum};
+use datafusion_expr::{
+    case, cast,
+    expr::{GroupingSet, WindowFunction},
+    ident, is_null, lit, max, min,
+    utils::disjunction,
+    when, AggregateExt, BuiltInWindowFunction, Expr, WindowFrame, WindowFunctionDefinition,
+};
+use datafusion_functions_aggregate::expr_fn::{
+    approx_distinct, approx_percentile_cont, avg, count, count_distinct, median, stddev, sum,
+};
 
-use crate::ReplDisplay;
+use crate::{
+    backend::{
+        describe::{DescribeMethod, Described},
+        output::format_batches,
+    },
+    cli::OutputFormat,
+    error::DescribeError,
+    BackendError, ReplDisplay,
+};
 
 pub struct DescribeDataFrame {
     df: DataFrame,
-    functions: &'static [&'static str],
-    describe_schema: SchemaRef,
+    methods: Vec<DescribeMethod>,
 }
 
 impl ReplDisplay for RecordBatch {
-    async fn display(self) -> anyhow::Result<String> {
-        let data = pretty_format_batches(&[self])?;
-        Ok(data.to_string())
+    async fn display(self, format: OutputFormat) -> Result<String, BackendError> {
+        Ok(format_batches(self.schema(), &[self], format)?)
     }
 }
 
 impl DescribeDataFrame {
-    pub fn new(df: DataFrame) -> Self {
-        let functions = &["count", "null_count", "mean", "std", "min", "max", "median"];
+    pub fn new(df: DataFrame, methods: &[DescribeMethod]) -> Self {
+        Self {
+            df,
+            methods: methods.to_vec(),
+        }
+    }
 
-        let original_schema_fields = df.schema().fields().iter();
+    /// Compute every statistic of every column with a single aggregate plan,
+    /// so the dataset is only scanned once. The most frequent values are the
+    /// exception, they take a second plan grouping every column at once.
+    pub async fn describe(&self) -> Result<Described<RecordBatch>, DescribeError> {
+        let original_schema_fields = self.df.schema().fields();
 
-        // define describe column
-        let mut describe_schemas = vec![Field::new("describe", DataType::Utf8, false)];
-        describe_schemas.extend(original_schema_fields.clone().map(|field| {
-            if field.data_type().is_numeric() {
-                Field::new(field.name(), DataType::Float64, true)
-            } else {
-                Field::new(field.name(), DataType::Utf8, true)
+        let mut aggr_exprs = vec![];
+        for (i, field) in original_schema_fields.iter().enumerate() {
+            for method in &self.methods {
+                if !supports(*method, field.data_type()) {
+                    continue;
+                }
+                if let Some(expr) = aggr_expr(*method, field.name(), field.data_type())
+                    .map_err(DescribeError::Aggregate)?
+                {
+                    aggr_exprs.push(expr.alias(method.alias(i)));
+                }
             }
-        }));
+        }
 
-        Self {
-            df,
-            functions,
-            describe_schema: Arc::new(Schema::new(describe_schemas)),
+        let stats = if aggr_exprs.is_empty() {
+            None
+        } else {
+            let batches = self
+                .df
+                .clone()
+                .aggregate(vec![], aggr_exprs)
+                .map_err(DescribeError::Aggregate)?
+                .collect()
+                .await
+                .map_err(DescribeError::Aggregate)?;
+            let schema = batches
+                .first()
+                .map(|b| b.schema())
+                .unwrap_or_else(|| Arc::new(Schema::empty()));
+            Some(concat_batches(&schema, &batches).map_err(DescribeError::Build)?)
+        };
+
+        let columns = original_schema_fields
+            .iter()
+            .enumerate()
+            .filter(|(_, field)| supports(DescribeMethod::Top, field.data_type()))
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        let tops = if self.methods.iter().any(|m| is_top(*m)) && !columns.is_empty() {
+            self.top_values(&columns).await?
+        } else {
+            vec![]
+        };
+        let mut statistics = HashMap::new();
+        if let Some(stats) = &stats {
+            for (field, column) in stats.schema().fields().iter().zip(stats.columns()) {
+                statistics.insert(field.name().clone(), column.clone());
+            }
         }
-    }
+        statistics.extend(tops);
 
-    pub async fn to_record_batch(&self) -> anyhow::Result<RecordBatch> {
-        let original_schema_fields = self.df.schema().fields().iter();
-
-        let describe_record_batch = vec![
-            self.count(),
-            self.null_count(),
-            self.mean(),
-            self.stddev(),
-            self.min(),
-            self.max(),
-            self.median(),
-        ];
+        let (values, others): (Vec<_>, Vec<_>) = self.methods.iter().partition(|m| m.is_value());
+        let table = |methods: &[DescribeMethod], own_type| {
+            (!methods.is_empty())
+                .then(|| self.table(methods, own_type, &statistics))
+                .transpose()
+        };
+        Ok(Described {
+            statistics: table(&others, false)?,
+            values: table(&values, true)?,
+        })
+    }
 
-        let mut array_ref_vec: Vec<ArrayRef> = vec![Arc::new(StringArray::from(
-            self.functions
-                .iter()
-                .map(|v| v.to_string())
-                .collect::<Vec<_>>(),
+    /// The statistics of the columns as a table with one row per method, in the type of
+    /// the column or as floats.
+    fn table(
+        &self,
+        methods: &[DescribeMethod],
+        own_type: bool,
+        statistics: &HashMap<String, ArrayRef>,
+    ) -> Result<RecordBatch, DescribeError> {
+        let mut fields = vec![Field::new("describe", DataType::Utf8, false)];
+        let mut columns: Vec<ArrayRef> = vec![Arc::new(StringArray::from(
+            methods.iter().map(label).collect::<Vec<_>>(),
         ))];
-        for field in original_schema_fields {
-            let mut array_data = vec![];
-            for result in describe_record_batch.iter() {
-                let array_ref = match result {
-                    Ok(df) => {
-                        let batchs = df.clone().collect().await;
-                        match batchs {
-                            Ok(batchs)
-                                if batchs.len() == 1
-                                    && batchs[0].column_by_name(field.name()).is_some() =>
-                            {
-                                let column = batchs[0].column_by_name(field.name()).unwrap();
-                                if field.data_type().is_numeric() {
-                                    cast(column, &DataType::Float64)?
-                                } else {
-                                    cast(column, &DataType::Utf8)?
-                                }
-                            }
-                            _ => Arc::new(StringArray::from(vec!["null"])),
-                        }
-                    }
-                    // Handling error when only boolean/binary column, and in other cases
-                    Err(err)
-                        if err.to_string().contains(
-                            "Error during planning: \
-                                            Aggregate requires at least one grouping \
-                                            or aggregate expression",
-                        ) =>
-                    {
-                        Arc::new(StringArray::from(vec!["null"]))
-                    }
-                    Err(other_err) => {
-                        panic!("{other_err}")
-                    }
-                };
-                array_data.push(array_ref);
+        for (i, field) in self.df.schema().fields().iter().enumerate() {
+            // the values of nested columns are not computed, nor written in every format
+            if own_type && !methods.iter().any(|m| supports(*m, field.data_type())) {
+                continue;
             }
-            array_ref_vec.push(concat(
-                array_data
+            let data_type = if own_type {
+                field.data_type().clone()
+            } else {
+                DataType::Float64
+            };
+            let values =
+                methods
                     .iter()
-                    .map(|af| af.as_ref())
-                    .collect::<Vec<_>>()
-                    .as_slice(),
-            )?);
+                    .map(|method| match statistics.get(&method.alias(i)) {
+                        Some(column) => arrow::compute::cast(&column.slice(0, 1), &data_type)
+                            .map_err(|e| DescribeError::Statistic {
+                                method: method.name(),
+                                column: field.name().to_string(),
+                                source: e,
+                            }),
+                        None => Ok(new_null_array(&data_type, 1)),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+            let values = values.iter().map(|v| v.as_ref()).collect::<Vec<_>>();
+            columns.push(concat(&values).map_err(DescribeError::Build)?);
+            fields.push(Field::new(field.name(), data_type, true));
         }
-
-        let describe_record_batch =
-            RecordBatch::try_new(self.describe_schema.clone(), array_ref_vec)?;
-
-        Ok(describe_record_batch)
+        RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).map_err(DescribeError::Build)
     }
 
-    fn count(&self) -> anyhow::Result<DataFrame> {
-        let original_schema_fields = self.df.schema().fields().iter();
+    /// The most frequent value of the columns and their counts, nulls left out, as
+    /// `(alias, statistic)`. Every column is grouped in a grouping set of its own, so the
+    /// dataset is only scanned once.
+    async fn top_values(
+        &self,
+        columns: &[usize],
+    ) -> Result<Vec<(String, ArrayRef)>, DescribeError> {
+        let fields = self.df.schema().fields();
+        let columns = columns
+            .iter()
+            .map(|&i| (i, fields[i].name()))
+            .collect::<Vec<_>>();
 
-        let ret = self.df.clone().aggregate(
+        // the other columns are null in the rows of a set, the groups of nulls are left out
+        // so the set of a row is its only column holding a value
+        let grouping_sets = columns.iter().map(|(_, name)| vec![ident(*name)]);
+        let values = columns.iter().map(|(_, name)| ident(*name).is_not_null());
+        let mut set = when(ident(columns[0].1).is_not_null(), lit(columns[0].0 as u64));
+        for (i, name) in &columns[1..] {
+            set.when(ident(*name).is_not_null(), lit(*i as u64));
+        }
+        let set = set.end().map_err(DescribeError::Aggregate)?;
+
+        // the values are ordered by their own type
+        let mut order_by = vec![ident(TOP_COUNT).sort(false, false)];
+        order_by.extend(
+            columns
+                .iter()
+                .map(|(_, name)| ident(*name).sort(true, false)),
+        );
+        let rank = Expr::WindowFunction(WindowFunction::new(
+            WindowFunctionDefinition::BuiltInWindowFunction(BuiltInWindowFunction::RowNumber),
             vec![],
-            original_schema_fields
-                .clone()
-                .map(|f| count(col(f.name())).alias(f.name()))
-                .collect::<Vec<_>>(),
-        )?;
-        Ok(ret)
+            vec![set],
+            order_by,
+            WindowFrame::new(Some(false)),
+            None,
+        ))
+        .alias(TOP_RANK);
+
+        let batches = self
+            .df
+            .clone()
+            .aggregate(
+                vec![Expr::GroupingSet(GroupingSet::GroupingSets(
+                    grouping_sets.collect(),
+                ))],
+                vec![count(lit(1)).alias(TOP_COUNT)],
+            )
+            .and_then(|df| df.filter(disjunction(values).expect("a column at least")))
+            .and_then(|df| df.window(vec![rank]))
+            .and_then(|df| df.filter(ident(TOP_RANK).eq(lit(1u64))))
+            .map_err(DescribeError::Aggregate)?
+            .collect()
+            .await
+            .map_err(DescribeError::Aggregate)?;
+
+        let mut tops = vec![];
+        for batch in batches {
+            let counts = batch.column_by_name(TOP_COUNT).expect("counted");
+            for (i, name) in &columns {
+                let column = batch.column_by_name(name).expect("grouped");
+                if let Some(row) = (0..batch.num_rows()).find(|&row| column.is_valid(row)) {
+                    tops.push((DescribeMethod::Top.alias(*i), column.slice(row, 1)));
+                    tops.push((DescribeMethod::TopCount.alias(*i), counts.slice(row, 1)));
+                }
+            }
+        }
+        Ok(tops)
     }
+}
 
-    fn null_count(&self) -> anyhow::Result<DataFrame> {
-        let original_schema_fields = self.df.schema().fields().iter();
+/// The columns of the most frequent values, named apart from the columns of the dataset.
+const TOP_COUNT: &str = "__top_count";
+const TOP_RANK: &str = "__top_rank";
 
-        let ret = self.df.clone().aggregate(
-            vec![],
-            original_schema_fields
-                .clone()
-                .map(|f| {
-                    sum(case(is_null(col(f.name())))
-                        .when(lit(true), lit(1))
-                        .otherwise(lit(0))
-                        .unwrap())
-                    .alias(f.name())
-                })
-                .collect::<Vec<_>>(),
-        )?;
-        Ok(ret)
+/// The name of a statistic in the output, percentiles are approximations.
+fn label(method: &DescribeMethod) -> String {
+    match method {
+        DescribeMethod::Percentile(p) => format!("approx_p{}", p),
+        method => method.name(),
     }
+}
 
-    fn mean(&self) -> anyhow::Result<DataFrame> {
-        let original_schema_fields = self.df.schema().fields().iter();
+fn is_top(method: DescribeMethod) -> bool {
+    matches!(method, DescribeMethod::Top | DescribeMethod::TopCount)
+}
 
-        let ret = self.df.clone().aggregate(
-            vec![],
-            original_schema_fields
-                .clone()
-                .filter(|f| f.data_type().is_numeric())
-                .map(|f| avg(col(f.name())).alias(f.name()))
-                .collect::<Vec<_>>(),
-        )?;
-        Ok(ret)
+fn is_string(data_type: &DataType) -> bool {
+    matches!(data_type, DataType::Utf8 | DataType::LargeUtf8)
+}
+
+fn supports(method: DescribeMethod, data_type: &DataType) -> bool {
+    match method {
+        DescribeMethod::Count | DescribeMethod::NullCount => true,
+        DescribeMethod::Mean
+        | DescribeMethod::Std
+        | DescribeMethod::Median
+        | DescribeMethod::Percentile(_) => data_type.is_numeric(),
+        DescribeMethod::Min | DescribeMethod::Max => {
+            data_type.is_numeric() || data_type.is_temporal() || is_string(data_type)
+        }
+        DescribeMethod::ApproxDistinct
+        | DescribeMethod::Distinct
+        | DescribeMethod::Top
+        | DescribeMethod::TopCount => !data_type.is_nested(),
+        DescribeMethod::MinLength | DescribeMethod::AvgLength | DescribeMethod::MaxLength => {
+            is_string(data_type)
+        }
+        DescribeMethod::TrueCount | DescribeMethod::FalseCount => data_type == &DataType::Boolean,
     }
+}
 
-    fn stddev(&self) -> anyhow::Result<DataFrame> {
-        let original_schema_fields = self.df.schema().fields().iter();
+/// The aggregate of a statistic, the most frequent values are not computed with one.
+fn aggr_expr(method: DescribeMethod, name: &str, data_type: &DataType) -> DFResult<Option<Expr>> {
+    let column = ident(name);
+    Ok(Some(match method {
+        DescribeMethod::Count => count(column),
+        DescribeMethod::NullCount => sum(case(is_null(column))
+            .when(lit(true), lit(1))
+            .otherwise(lit(0))?),
+        DescribeMethod::Mean => avg(column),
+        DescribeMethod::Std => stddev(column),
+        DescribeMethod::Min => min(column),
+        DescribeMethod::Max => max(column),
+        DescribeMethod::Median => median(column),
+        // the sketch reads null slots as zeros, they are filtered out
+        DescribeMethod::Percentile(p) => approx_percentile_cont(
+            cast(column.clone(), DataType::Float64),
+            lit(p as f64 / 100.0),
+        )
+        .filter(column.is_not_null())
+        .build()?,
+        // the sketch takes integers, strings and binaries, other values are counted as strings
+        DescribeMethod::ApproxDistinct if data_type.is_integer() || is_string(data_type) => {
+            approx_distinct::approx_distinct(column)
+        }
+        DescribeMethod::ApproxDistinct => {
+            approx_distinct::approx_distinct(cast(column, DataType::Utf8))
+        }
+        DescribeMethod::Distinct => count_distinct(column),
+        DescribeMethod::MinLength => min(character_length(column)),
+        DescribeMethod::AvgLength => avg(character_length(column)),
+        DescribeMethod::MaxLength => max(character_length(column)),
+        DescribeMethod::TrueCount => sum(case(column).when(lit(true), lit(1)).otherwise(lit(0))?),
+        DescribeMethod::FalseCount => {
+            sum(case(column).when(lit(false), lit(1)).otherwise(lit(0))?)
+        }
+        DescribeMethod::Top | DescribeMethod::TopCount => return Ok(None),
+    }))
+}
 
-        let ret = self.df.clone().aggregate(
-            vec![],
-            original_schema_fields
-                .clone()
-                .filter(|f| f.data_type().is_numeric())
-                .map(|f| stddev(col(f.name())).alias(f.name()))
-                .collect::<Vec<_>>(),
-        )?;
-        Ok(ret)
+#[cfg(test)]
+mod tests {
+    use arrow::array::{BooleanArray, Date32Array, Int64Array};
+    use datafusion::{datasource::MemTable, prelude::SessionContext};
+
+    use super::*;
+
+    fn users() -> RecordBatch {
+        RecordBatch::try_from_iter(vec![
+            (
+                "id",
+                Arc::new(Int64Array::from(vec![
+                    Some(4),
+                    Some(1),
+                    None,
+                    Some(3),
+                    Some(2),
+                ])) as ArrayRef,
+            ),
+            (
+                "name",
+                Arc::new(StringArray::from(vec![
+                    Some("bb"),
+                    Some("a"),
+                    Some("bb"),
+                    None,
+                    Some("a"),
+                ])),
+            ),
+            (
+                "active",
+                Arc::new(BooleanArray::from(vec![
+                    Some(true),
+                    Some(false),
+                    Some(true),
+                    Some(true),
+                    None,
+                ])),
+            ),
+        ])
+        .unwrap()
     }
 
-    fn min(&self) -> anyhow::Result<DataFrame> {
-        let original_schema_fields = self.df.schema().fields().iter();
-        let ret = self.df.clone().aggregate(
-            vec![],
-            original_schema_fields
-                .clone()
-                .filter(|f| !matches!(f.data_type(), DataType::Binary | DataType::Boolean))
-                .map(|f| min(col(f.name())).alias(f.name()))
-                .collect::<Vec<_>>(),
-        )?;
-        Ok(ret)
+    async fn describe(batch: RecordBatch, methods: &[DescribeMethod]) -> String {
+        let ctx = SessionContext::new();
+        let table = MemTable::try_new(batch.schema(), vec![vec![batch]]).unwrap();
+        ctx.register_table("t", Arc::new(table)).unwrap();
+        let df = ctx.table("t").await.unwrap();
+        let described = DescribeDataFrame::new(df, methods)
+            .describe()
+            .await
+            .unwrap();
+        described.display(OutputFormat::Csv).await.unwrap()
     }
 
-    fn max(&self) -> anyhow::Result<DataFrame> {
-        let original_schema_fields = self.df.schema().fields().iter();
-        let ret = self.df.clone().aggregate(
-            vec![],
-            original_schema_fields
-                .clone()
-                .filter(|f| !matches!(f.data_type(), DataType::Binary | DataType::Boolean))
-                .map(|f| max(col(f.name())).alias(f.name()))
-                .collect::<Vec<_>>(),
-        )?;
-        Ok(ret)
+    #[tokio::test]
+    async fn describe_should_compute_the_statistics_of_every_column() {
+        let methods = [
+            DescribeMethod::Count,
+            DescribeMethod::NullCount,
+            DescribeMethod::Min,
+            DescribeMethod::Max,
+            DescribeMethod::Percentile(50),
+            DescribeMethod::Distinct,
+            DescribeMethod::Top,
+            DescribeMethod::TopCount,
+            DescribeMethod::MinLength,
+            DescribeMethod::MaxLength,
+            DescribeMethod::TrueCount,
+            DescribeMethod::FalseCount,
+        ];
+        assert_eq!(
+            describe(users(), &methods).await,
+            "\
+describe,id,name,active
+count,4.0,4.0,4.0
+null_count,1.0,1.0,1.0
+approx_p50,2.5,,
+distinct,4.0,2.0,2.0
+top_count,1.0,2.0,3.0
+min_length,,1.0,
+max_length,,2.0,
+true_count,,,3.0
+false_count,,,1.0
+
+describe,id,name,active
+min,1,a,
+max,4,bb,
+top,1,a,true"
+        );
     }
 
-    fn median(&self) -> anyhow::Result<DataFrame> {
-        let original_schema_fields = self.df.schema().fields().iter();
-        let ret = self.df.clone().aggregate(
-            vec![],
-            original_schema_fields
-                .clone()
-                .filter(|f| f.data_type().is_numeric())
-                .map(|f| median(col(f.name())).alias(f.name()))
-                .collect::<Vec<_>>(),
-        )?;
-        Ok(ret)
+    #[tokio::test]
+    async fn describe_should_keep_values_in_their_type() {
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "id",
+                Arc::new(Int64Array::from(vec![
+                    9_007_199_254_740_993,
+                    9_007_199_254_740_993,
+                    1,
+                ])) as ArrayRef,
+            ),
+            (
+                "day",
+                Arc::new(Date32Array::from(vec![19964, 19965, 19965])),
+            ),
+        ])
+        .unwrap();
+        let methods = [
+            DescribeMethod::Min,
+            DescribeMethod::Max,
+            DescribeMethod::Top,
+        ];
+        assert_eq!(
+            describe(batch, &methods).await,
+            "\
+describe,id,day
+min,1,2024-08-29
+max,9007199254740993,2024-08-30
+top,9007199254740993,2024-08-30"
+        );
     }
 }
@@ -1,73 +1,258 @@
 mod describe;
 mod df_describe;
+mod mysql;
+mod options;
+mod postgres;
+mod save;
+mod sql_table;
+mod sqlite;
 
-use std::ops::Deref;
+use std::{collections::BTreeMap, fs::File, ops::Deref, sync::Arc};
 
-use arrow_cast::pretty::pretty_format_batches;
-use datafusion::prelude::{CsvReadOptions, NdJsonReadOptions, SessionConfig, SessionContext};
+use arrow::array::AsArray;
+use arrow::csv::reader::Format;
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::ipc::reader::StreamReader;
+use datafusion::catalog::CatalogProvider;
+use datafusion::dataframe::DataFrame;
+use datafusion::datasource::file_format::DEFAULT_SCHEMA_INFER_MAX_RECORD;
+use datafusion::datasource::{file_format::options::ArrowReadOptions, MemTable};
+use datafusion::error::DataFusionError;
+
+use datafusion::prelude::{
+    cast, ident, lit, nullif, CsvReadOptions, NdJsonReadOptions, ParquetReadOptions, SessionConfig,
+    SessionContext,
+};
 use df_describe::DescribeDataFrame;
+use mysql::register_mysql;
+use options::{set_option, show_options};
+use postgres::register_postgres;
+use regex::Regex;
+use save::save_dataframe;
+use sqlite::register_sqlite;
+
+use crate::{
+    backend::{
+        avro::read_avro_batches, excel::read_workbook, metadata::data_files,
+        output::format_batches, Catalog, DescribeMethod,
+    },
+    cli::{split_glob, DatasetConn, FileOpts, IpcFormat, OutputFormat, ReadOpts, SaveFile},
+    Backend, BackendError, DataFusionConfig, ReplDisplay,
+};
 
-use crate::{cli::DatasetConn, Backend, ReplDisplay};
+/// Tables of a whole database are registered under their own schema.
+const TABLE_NAME: &str =
+    "CASE WHEN table_schema = 'public' THEN table_name ELSE table_schema || '.' || table_name END";
 
 pub struct DataFusionBackend(SessionContext);
 
 impl DataFusionBackend {
-    pub fn new() -> Self {
+    pub fn new(datafusion: &DataFusionConfig) -> Self {
         let mut config = SessionConfig::new();
         config.options_mut().catalog.information_schema = true;
+        if let Some(batch_size) = datafusion.batch_size {
+            config = config.with_batch_size(batch_size);
+        }
+        if let Some(target_partitions) = datafusion.target_partitions {
+            config = config.with_target_partitions(target_partitions);
+        }
         let ctx = SessionContext::new_with_config(config);
         Self(ctx)
     }
+
+    /// The catalog databases register their schema in.
+    fn default_catalog(&self) -> Result<Arc<dyn CatalogProvider>, BackendError> {
+        let name = self
+            .state()
+            .config_options()
+            .catalog
+            .default_catalog
+            .clone();
+        self.0
+            .catalog(&name)
+            .ok_or_else(|| DataFusionError::Internal(format!("Catalog {} not found", name)).into())
+    }
 }
 
 impl Backend for DataFusionBackend {
-    async fn connect(&mut self, opts: &crate::cli::ConnectOpts) -> anyhow::Result<()> {
-        match &opts.conn {
-            DatasetConn::Postgres(_conn_str) => {
-                println!("Postgres is not supported yet")
+    async fn connect(&mut self, opts: &crate::cli::ConnectOpts) -> Result<(), BackendError> {
+        match opts.dataset()? {
+            DatasetConn::Postgres(conn_str) => {
+                register_postgres(self, &conn_str, &opts.name, opts.table.as_deref())
+                    .await
+                    .map_err(BackendError::Connector)?;
+            }
+            DatasetConn::MySql(conn_str) => {
+                register_mysql(self, &conn_str, &opts.name, opts.table.as_deref())
+                    .await
+                    .map_err(BackendError::Connector)?;
+            }
+            DatasetConn::Sqlite(path) => {
+                register_sqlite(self, &path, &opts.name, opts.table.as_deref())
+                    .await
+                    .map_err(BackendError::Connector)?;
             }
             DatasetConn::Csv(file_opts) => {
+                let read = &opts.read;
+                let mut schema = read_schema(read);
+                // DataFusion has no null string for csv, so the columns are read as strings,
+                // then cast to their type once the null strings are replaced
+                let typed = match &read.null {
+                    Some(null) => {
+                        let typed = match schema.take() {
+                            Some(schema) => schema,
+                            None => infer_csv_schema(&file_opts, read, null)?,
+                        };
+                        schema = Some(string_schema(&typed));
+                        Some((null, typed))
+                    }
+                    None => None,
+                };
+                let defaults = CsvReadOptions::default();
                 let csv_opts = CsvReadOptions {
+                    has_header: !read.no_header,
+                    delimiter: read.delimiter.unwrap_or(defaults.delimiter),
+                    quote: read.quote.unwrap_or(defaults.quote),
+                    escape: read.escape,
+                    comment: read.comment,
+                    schema: schema.as_ref(),
+                    schema_infer_max_records: read
+                        .infer_rows
+                        .unwrap_or(defaults.schema_infer_max_records),
                     file_extension: &file_opts.ext,
                     file_compression_type: file_opts.compression,
+                    table_partition_cols: file_opts.partitions.clone(),
                     ..Default::default()
                 };
-                self.register_csv(&opts.name, &file_opts.filename, csv_opts)
-                    .await?;
+                let path = listing_path(&file_opts)?;
+                match typed {
+                    Some((null, typed)) => {
+                        let df = self.read_csv(path, csv_opts).await?;
+                        let df = replace_null_strings(df, &typed, null)?;
+                        self.register_table(opts.name.as_str(), df.into_view())?;
+                    }
+                    None => self.register_csv(&opts.name, &path, csv_opts).await?,
+                }
             }
-            DatasetConn::Parquet(filename) => {
-                self.register_parquet(&opts.name, filename, Default::default())
+            DatasetConn::Parquet(file_opts) => {
+                let parquet_opts = ParquetReadOptions {
+                    file_extension: &file_opts.ext,
+                    table_partition_cols: file_opts.partitions.clone(),
+                    ..Default::default()
+                };
+                self.register_parquet(&opts.name, &listing_path(&file_opts)?, parquet_opts)
                     .await?;
             }
             DatasetConn::NdJson(file_opts) => {
+                let schema = read_schema(&opts.read);
+                let defaults = NdJsonReadOptions::default();
                 let json_opts = NdJsonReadOptions {
+                    schema: schema.as_ref(),
+                    schema_infer_max_records: opts
+                        .read
+                        .infer_rows
+                        .unwrap_or(defaults.schema_infer_max_records),
                     file_extension: &file_opts.ext,
                     file_compression_type: file_opts.compression,
+                    table_partition_cols: file_opts.partitions.clone(),
+                    ..Default::default()
+                };
+                self.register_json(&opts.name, &listing_path(&file_opts)?, json_opts)
+                    .await?;
+            }
+            DatasetConn::Arrow(file_opts, IpcFormat::File) => {
+                let arrow_opts = ArrowReadOptions {
+                    file_extension: &file_opts.ext,
+                    table_partition_cols: file_opts.partitions.clone(),
                     ..Default::default()
                 };
-                self.register_json(&opts.name, &file_opts.filename, json_opts)
+                self.register_arrow(&opts.name, &listing_path(&file_opts)?, arrow_opts)
                     .await?;
             }
+            DatasetConn::Arrow(file_opts, IpcFormat::Stream) => {
+                // listing tables only read the file format, streams are read into memory
+                let path = file_opts.single_file("arrow streams")?;
+                let reader = StreamReader::try_new(File::open(path)?, None)?;
+                let schema = reader.schema();
+                let batches = reader.collect::<Result<Vec<_>, _>>()?;
+                let table = MemTable::try_new(schema, vec![batches])?;
+                self.register_table(opts.name.as_str(), Arc::new(table))?;
+            }
+            DatasetConn::Excel(file_opts) => {
+                let batch = read_workbook(&file_opts, &opts.read)?;
+                self.register_batch(opts.name.as_str(), batch)?;
+            }
+            DatasetConn::Avro(file_opts) => {
+                let (schema, batches) = read_avro_batches(&file_opts)?;
+                let table = MemTable::try_new(schema, vec![batches])?;
+                self.register_table(opts.name.as_str(), Arc::new(table))?;
+            }
         }
         Ok(())
     }
-    async fn list(&self) -> anyhow::Result<impl ReplDisplay> {
-        let sql = "SELECT table_name, table_type FROM information_schema.tables WHERE table_schema = 'public'";
-        let df = self.0.sql(sql).await?;
-        Ok(df)
+    async fn disconnect(&mut self, name: &str) -> Result<(), BackendError> {
+        if self.deregister_table(name)?.is_some() {
+            return Ok(());
+        }
+        match self.default_catalog()?.deregister_schema(name, true)? {
+            Some(_) => Ok(()),
+            None => Err(BackendError::DatasetNotFound(name.to_string())),
+        }
+    }
+    async fn rename(&mut self, from: &str, to: &str) -> Result<(), BackendError> {
+        // tables and schemas made with SQL are not known to the registry
+        let catalog = self.default_catalog()?;
+        if self.table_exist(to)? || catalog.schema(to).is_some() {
+            return Err(BackendError::DatasetExists(to.to_string()));
+        }
+
+        if let Some(table) = self.deregister_table(from)? {
+            // put the table back under its name if the new one is refused
+            if let Err(e) = self.register_table(to, table.clone()) {
+                self.register_table(from, table)?;
+                return Err(e.into());
+            }
+            return Ok(());
+        }
+        match catalog.deregister_schema(from, true)? {
+            Some(schema) => {
+                if let Err(e) = catalog.register_schema(to, schema.clone()) {
+                    catalog.register_schema(from, schema)?;
+                    return Err(e.into());
+                }
+                Ok(())
+            }
+            None => Err(BackendError::DatasetNotFound(from.to_string())),
+        }
+    }
+    async fn tables(&self) -> Result<Vec<String>, BackendError> {
+        let sql = format!(
+            "SELECT {} FROM information_schema.tables WHERE table_schema != 'information_schema'",
+            TABLE_NAME
+        );
+        let mut tables = vec![];
+        for batch in self.0.sql(&sql).await?.collect().await? {
+            for name in batch.column(0).as_string::<i32>().iter().flatten() {
+                tables.push(name.to_string());
+            }
+        }
+        Ok(tables)
     }
-    async fn schema(&self, name: &str) -> anyhow::Result<impl ReplDisplay> {
+    async fn schema(&self, name: &str) -> Result<impl ReplDisplay, BackendError> {
         let df = self.0.sql(&format!("DESCRIBE {}", name)).await?;
         Ok(df)
     }
-    async fn describe(&self, name: &str) -> anyhow::Result<impl ReplDisplay> {
+    async fn describe(
+        &self,
+        name: &str,
+        methods: &[DescribeMethod],
+    ) -> Result<impl ReplDisplay, BackendError> {
         let df = self.0.sql(&format!("SELECT * FROM {}", name)).await?;
         // let df = df.describe().await?;
-        let df = DescribeDataFrame::new(df);
-        let batch = df.to_record_batch().await?;
-        Ok(batch)
+        let df = DescribeDataFrame::new(df, methods);
+        Ok(df.describe().await?)
     }
-    async fn head(&self, name: &str, size: usize) -> anyhow::Result<impl ReplDisplay> {
+    async fn head(&self, name: &str, size: usize) -> Result<impl ReplDisplay, BackendError> {
         let df = self
             .0
             .sql(&format!("SELECT * FROM {} LIMIT {}", name, size))
@@ -75,7 +260,7 @@ impl Backend for DataFusionBackend {
         Ok(df)
     }
 
-    async fn sql(&self, sql: &str) -> anyhow::Result<impl ReplDisplay> {
+    async fn sql(&self, sql: &str) -> Result<impl ReplDisplay, BackendError> {
         // Why can not here use self.sql?
         // recursion in an async fn requires boxing a recursive `async fn` call must introduce indirection such as `Box::pin` to avoid an infinitely sized future
         /*
@@ -99,11 +284,193 @@ impl Backend for DataFusionBackend {
         let df = self.0.sql(sql).await?;
         Ok(df)
     }
+
+    async fn save(&self, sql: &str, file: &SaveFile) -> Result<usize, BackendError> {
+        let df = self.0.sql(sql).await?;
+        save_dataframe(df, file).await
+    }
+
+    async fn catalog(&self) -> Result<Catalog, BackendError> {
+        let mut datasets = self
+            .tables()
+            .await?
+            .into_iter()
+            .map(|name| (name, vec![]))
+            .collect::<BTreeMap<_, _>>();
+        let sql = format!(
+            "SELECT {}, column_name FROM information_schema.columns WHERE table_schema != 'information_schema' ORDER BY ordinal_position",
+            TABLE_NAME
+        );
+        for batch in self.0.sql(&sql).await?.collect().await? {
+            let names = batch.column(0).as_string::<i32>();
+            let columns = batch.column(1).as_string::<i32>();
+            for (name, column) in names.iter().zip(columns.iter()) {
+                if let (Some(name), Some(column)) = (name, column) {
+                    datasets
+                        .entry(name.to_string())
+                        .or_default()
+                        .push(column.to_string());
+                }
+            }
+        }
+
+        let state = self.0.state();
+        let functions = state
+            .scalar_functions()
+            .keys()
+            .chain(state.aggregate_functions().keys())
+            .chain(state.window_functions().keys())
+            .cloned()
+            .collect();
+        Ok(Catalog {
+            datasets,
+            functions,
+        })
+    }
+
+    async fn set_option(&mut self, key: &str, value: &str) -> Result<String, BackendError> {
+        set_option(&self.0, key, value)
+    }
+
+    async fn show_options(&self, key: Option<&str>) -> Result<impl ReplDisplay, BackendError> {
+        show_options(&self.0, key)
+    }
+}
+
+/// The file schema given with `--schema`, the partition columns are added by DataFusion.
+fn read_schema(read: &ReadOpts) -> Option<Schema> {
+    read.schema.as_ref().map(|schema| {
+        Schema::new(
+            schema
+                .columns
+                .iter()
+                .map(|(name, data_type)| Field::new(name, data_type.clone(), true))
+                .collect::<Vec<_>>(),
+        )
+    })
+}
+
+/// The schema of csv files, inferred with the `null` strings left out,
+/// from the first rows of the files in order.
+fn infer_csv_schema(
+    file_opts: &FileOpts,
+    read: &ReadOpts,
+    null: &str,
+) -> Result<Schema, BackendError> {
+    let null = Regex::new(&format!("^{}$", regex::escape(null))).expect("escaped regex");
+    let mut format = Format::default()
+        .with_header(!read.no_header)
+        .with_null_regex(null);
+    if let Some(delimiter) = read.delimiter {
+        format = format.with_delimiter(delimiter);
+    }
+    if let Some(quote) = read.quote {
+        format = format.with_quote(quote);
+    }
+    if let Some(escape) = read.escape {
+        format = format.with_escape(escape);
+    }
+    if let Some(comment) = read.comment {
+        format = format.with_comment(comment);
+    }
+
+    let mut remaining = read.infer_rows.unwrap_or(DEFAULT_SCHEMA_INFER_MAX_RECORD);
+    let mut schemas = vec![];
+    for file in data_files(file_opts)? {
+        let reader = file_opts.compression.convert_read(File::open(&file)?)?;
+        let (schema, rows) = format.infer_schema(reader, Some(remaining))?;
+        schemas.push(schema);
+        remaining = remaining.saturating_sub(rows);
+        if remaining == 0 {
+            break;
+        }
+    }
+    let Some(first) = schemas.first() else {
+        return Err(BackendError::Unsupported(
+            "--null without files to infer the schema from".to_string(),
+        ));
+    };
+
+    // columns only holding nulls in a file take the type of the other files, and like
+    // DataFusion, columns of several types are floats if they are numbers, strings otherwise
+    let fields = first.fields().iter().enumerate().map(|(i, field)| {
+        let types = schemas
+            .iter()
+            .filter_map(|s| s.fields().get(i).map(|f| f.data_type()))
+            .filter(|t| **t != DataType::Null)
+            .collect::<Vec<_>>();
+        let data_type = match types.first() {
+            Some(first) if types.iter().all(|t| t == first) => (*first).clone(),
+            Some(_)
+                if types
+                    .iter()
+                    .all(|t| matches!(t, DataType::Int64 | DataType::Float64)) =>
+            {
+                DataType::Float64
+            }
+            _ => DataType::Utf8,
+        };
+        Field::new(field.name(), data_type, true)
+    });
+    Ok(Schema::new(fields.collect::<Vec<_>>()))
+}
+
+/// The same columns, all read as strings.
+fn string_schema(schema: &Schema) -> Schema {
+    Schema::new(
+        schema
+            .fields()
+            .iter()
+            .map(|f| Field::new(f.name(), DataType::Utf8, true))
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// Replace the `null` strings of the file columns with nulls and cast them to their type,
+/// the partition columns following them are kept as they are.
+fn replace_null_strings(
+    df: DataFrame,
+    typed: &Schema,
+    null: &str,
+) -> Result<DataFrame, BackendError> {
+    let columns =
+        df.schema()
+            .fields()
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                let column = ident(field.name());
+                match typed.fields().get(i) {
+                    Some(typed) => cast(nullif(column, lit(null)), typed.data_type().clone())
+                        .alias(field.name()),
+                    None => column,
+                }
+            })
+            .collect::<Vec<_>>();
+    Ok(df.select(columns)?)
+}
+
+/// DataFusion leaves the `key=value` directories out when matching a glob,
+/// so the glob of a partitioned dataset may only pick the file names.
+fn listing_path(file_opts: &FileOpts) -> Result<String, BackendError> {
+    let Some((base, segments)) = split_glob(&file_opts.filename) else {
+        return Ok(file_opts.filename.clone());
+    };
+    if file_opts.partitions.is_empty() {
+        return Ok(file_opts.filename.clone());
+    }
+    let (file, dirs) = segments.split_last().expect("a glob has a segment");
+    if dirs.iter().any(|dir| dir != "*" && !dir.ends_with("=*")) {
+        return Err(BackendError::Unsupported(
+            "globs selecting partitions with the datafusion engine, filter the partition columns in SQL instead".to_string(),
+        ));
+    }
+    Ok(base.join(file).display().to_string())
 }
 
 impl Default for DataFusionBackend {
     fn default() -> Self {
-        Self::new()
+        Self::new(&DataFusionConfig::default())
     }
 }
 
@@ -115,9 +482,9 @@ impl Deref for DataFusionBackend {
 }
 
 impl ReplDisplay for datafusion::dataframe::DataFrame {
-    async fn display(self) -> anyhow::Result<String> {
+    async fn display(self, format: OutputFormat) -> Result<String, BackendError> {
+        let schema = self.schema().inner().clone();
         let batches = self.collect().await?;
-        let data = pretty_format_batches(&batches)?;
-        Ok(data.to_string())
+        Ok(format_batches(schema, &batches, format)?)
     }
 }
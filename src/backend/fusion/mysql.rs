@@ -0,0 +1,232 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use arrow::array::{
+    ArrayRef, BinaryBuilder, Date32Builder, Float32Builder, Float64Builder, Int64Builder,
+    RecordBatch, RecordBatchOptions, StringBuilder, TimestampMicrosecondBuilder, UInt64Builder,
+};
+use arrow::datatypes::{DataType, Date32Type, SchemaRef, TimeUnit};
+use async_trait::async_trait;
+use chrono::{NaiveDate, NaiveDateTime};
+use datafusion::error::{DataFusionError, Result as DFResult};
+use datafusion::prelude::SessionContext;
+use datafusion::sql::unparser::dialect::{Dialect, MySqlDialect};
+use mysql_async::consts::{ColumnFlags, ColumnType};
+use mysql_async::prelude::{FromValue, Queryable};
+use mysql_async::{Pool, Row};
+
+use super::sql_table::{
+    quote_ident, quote_table, register_schema, SqlColumn, SqlExecutor, SqlTable,
+};
+use crate::backend::metadata::mask_password;
+
+/// The character set id mysql reports for binary strings and blobs.
+const BINARY_CHARSET: u16 = 63;
+
+/// Connect to the mysql database and register either the given table,
+/// or every table of the database under a schema named `name`.
+pub async fn register_mysql(
+    ctx: &SessionContext,
+    conn_str: &str,
+    name: &str,
+    table: Option<&str>,
+) -> anyhow::Result<()> {
+    let pool = Pool::from_url(conn_str).with_context(|| {
+        format!(
+            "Invalid mysql connection string: {}",
+            mask_password(conn_str)
+        )
+    })?;
+    let executor = Arc::new(MySqlExecutor {
+        pool,
+        dialect: MySqlDialect {},
+    });
+
+    match table {
+        Some(table) => {
+            let provider = executor.clone().table(table).await?;
+            ctx.register_table(name, Arc::new(provider))?;
+        }
+        None => {
+            let mut conn = executor.pool.get_conn().await.with_context(|| {
+                format!("Failed to connect to mysql: {}", mask_password(conn_str))
+            })?;
+            let names: Vec<String> = conn
+                .query(
+                    "SELECT table_name FROM information_schema.tables \
+                     WHERE table_schema = DATABASE() ORDER BY table_name",
+                )
+                .await?;
+            drop(conn);
+
+            let mut tables = vec![];
+            for table in names {
+                let provider = executor.clone().table(&table).await?;
+                tables.push((table, provider));
+            }
+            register_schema(ctx, name, tables)?;
+        }
+    }
+    Ok(())
+}
+
+struct MySqlExecutor {
+    pool: Pool,
+    dialect: MySqlDialect,
+}
+
+impl MySqlExecutor {
+    async fn table(self: Arc<Self>, table: &str) -> anyhow::Result<SqlTable> {
+        let mut conn = self
+            .pool
+            .get_conn()
+            .await
+            .context("Failed to connect to mysql")?;
+        let result = conn
+            .query_iter(format!(
+                "SELECT * FROM {} LIMIT 0",
+                quote_table(&self.dialect, table)
+            ))
+            .await
+            .with_context(|| format!("Failed to load table {}", table))?;
+
+        let columns = result
+            .columns_ref()
+            .iter()
+            .map(|c| {
+                let name = quote_ident(&self.dialect, &c.name_str());
+                let unsigned = c.flags().contains(ColumnFlags::UNSIGNED_FLAG);
+                let binary = c.character_set() == BINARY_CHARSET;
+                // string comparisons are collation dependent in mysql, so they stay in DataFusion
+                let (data_type, pushdown) = match c.column_type() {
+                    ColumnType::MYSQL_TYPE_TINY
+                    | ColumnType::MYSQL_TYPE_SHORT
+                    | ColumnType::MYSQL_TYPE_INT24
+                    | ColumnType::MYSQL_TYPE_LONG
+                    | ColumnType::MYSQL_TYPE_LONGLONG
+                    | ColumnType::MYSQL_TYPE_YEAR => {
+                        // mysql compares unsigned and signed values with its own coercion rules
+                        if unsigned {
+                            (DataType::UInt64, false)
+                        } else {
+                            (DataType::Int64, true)
+                        }
+                    }
+                    ColumnType::MYSQL_TYPE_FLOAT => (DataType::Float32, !unsigned),
+                    ColumnType::MYSQL_TYPE_DOUBLE => (DataType::Float64, !unsigned),
+                    // compared as exact decimals by mysql, as rounded floats by datafusion
+                    ColumnType::MYSQL_TYPE_DECIMAL | ColumnType::MYSQL_TYPE_NEWDECIMAL => {
+                        (DataType::Float64, false)
+                    }
+                    ColumnType::MYSQL_TYPE_DATE | ColumnType::MYSQL_TYPE_NEWDATE => {
+                        (DataType::Date32, true)
+                    }
+                    ColumnType::MYSQL_TYPE_DATETIME
+                    | ColumnType::MYSQL_TYPE_DATETIME2
+                    | ColumnType::MYSQL_TYPE_TIMESTAMP
+                    | ColumnType::MYSQL_TYPE_TIMESTAMP2 => {
+                        (DataType::Timestamp(TimeUnit::Microsecond, None), true)
+                    }
+                    ColumnType::MYSQL_TYPE_TINY_BLOB
+                    | ColumnType::MYSQL_TYPE_MEDIUM_BLOB
+                    | ColumnType::MYSQL_TYPE_LONG_BLOB
+                    | ColumnType::MYSQL_TYPE_BLOB
+                    | ColumnType::MYSQL_TYPE_VARCHAR
+                    | ColumnType::MYSQL_TYPE_VAR_STRING
+                    | ColumnType::MYSQL_TYPE_STRING
+                        if binary =>
+                    {
+                        (DataType::Binary, false)
+                    }
+                    // json, time, enum, set and the text types are read as text
+                    _ => (DataType::Utf8, false),
+                };
+                SqlColumn {
+                    name: c.name_str().to_string(),
+                    data_type,
+                    select: name,
+                    pushdown,
+                }
+            })
+            .collect();
+        result.drop_result().await?;
+
+        Ok(SqlTable::new(self, table, columns))
+    }
+}
+
+#[async_trait]
+impl SqlExecutor for MySqlExecutor {
+    fn dialect(&self) -> &dyn Dialect {
+        &self.dialect
+    }
+
+    async fn query(&self, sql: &str, schema: SchemaRef) -> DFResult<RecordBatch> {
+        let rows: Vec<Row> = async {
+            let mut conn = self.pool.get_conn().await?;
+            conn.query(sql).await
+        }
+        .await
+        .map_err(|e| DataFusionError::External(Box::new(e)))?;
+
+        let arrays = schema
+            .fields()
+            .iter()
+            .enumerate()
+            .map(|(i, field)| build_array(&rows, i, field.data_type()))
+            .collect::<DFResult<Vec<_>>>()?;
+        let options = RecordBatchOptions::new().with_row_count(Some(rows.len()));
+        Ok(RecordBatch::try_new_with_options(schema, arrays, &options)?)
+    }
+}
+
+fn get<T: FromValue>(row: &Row, idx: usize) -> DFResult<Option<T>> {
+    match row.get_opt::<Option<T>, _>(idx) {
+        Some(value) => value.map_err(|e| DataFusionError::External(Box::new(e))),
+        None => Err(DataFusionError::Execution(format!(
+            "Missing column {} in mysql row",
+            idx
+        ))),
+    }
+}
+
+fn build_array(rows: &[Row], idx: usize, data_type: &DataType) -> DFResult<ArrayRef> {
+    macro_rules! build {
+        ($builder:ty, $native:ty) => {
+            build!($builder, $native, |v| v)
+        };
+        ($builder:ty, $native:ty, $map:expr) => {{
+            let mut builder = <$builder>::new();
+            for row in rows {
+                builder.append_option(get::<$native>(row, idx)?.map($map));
+            }
+            builder.finish()
+        }};
+    }
+
+    let array: ArrayRef = match data_type {
+        DataType::Int64 => Arc::new(build!(Int64Builder, i64)),
+        DataType::UInt64 => Arc::new(build!(UInt64Builder, u64)),
+        DataType::Float32 => Arc::new(build!(Float32Builder, f32)),
+        DataType::Float64 => Arc::new(build!(Float64Builder, f64)),
+        DataType::Utf8 => Arc::new(build!(StringBuilder, String)),
+        DataType::Binary => Arc::new(build!(BinaryBuilder, Vec<u8>)),
+        DataType::Date32 => Arc::new(build!(
+            Date32Builder,
+            NaiveDate,
+            Date32Type::from_naive_date
+        )),
+        DataType::Timestamp(_, _) => {
+            Arc::new(build!(TimestampMicrosecondBuilder, NaiveDateTime, |t| {
+                t.and_utc().timestamp_micros()
+            }))
+        }
+        other => {
+            return Err(DataFusionError::NotImplemented(format!(
+                "Reading mysql column as {}",
+                other
+            )))
+        }
+    };
+    Ok(array)
+}
@@ -0,0 +1,278 @@
+use std::sync::Arc;
+
+use arrow::array::{RecordBatch, StringArray};
+use arrow::datatypes::{DataType, Field, Schema};
+use datafusion::config::{ConfigEntry, ConfigOptions};
+use datafusion::prelude::SessionContext;
+
+use crate::BackendError;
+
+/// Set a session option, `key` may omit the namespace if it is not ambiguous.
+/// Returns the full key of the option.
+pub fn set_option(ctx: &SessionContext, key: &str, value: &str) -> Result<String, BackendError> {
+    let state = ctx.state_ref();
+    let mut state = state.write();
+    let options = state.config_mut().options_mut();
+    let key = resolve_key(options, key)?;
+    options
+        .set(&key, value)
+        .map_err(|reason| BackendError::InvalidOptionValue {
+            key: key.clone(),
+            value: value.to_string(),
+            reason: Box::new(reason),
+        })?;
+    Ok(key)
+}
+
+/// The options matching `key` as a `name`, `value`, `description` table, all of them if omitted.
+/// `key` may also be a namespace like `datafusion.execution.parquet`.
+pub fn show_options(ctx: &SessionContext, key: Option<&str>) -> Result<RecordBatch, BackendError> {
+    let state = ctx.state();
+    let options = state.config_options();
+    let entries = match key {
+        Some(key) => {
+            let key = resolve_key(options, key).unwrap_or_else(|_| key.to_string());
+            let namespace = format!("{}.", key);
+            let matched = options
+                .entries()
+                .into_iter()
+                .filter(|e| e.key == key || e.key.starts_with(&namespace))
+                .collect::<Vec<_>>();
+            if matched.is_empty() {
+                return Err(unknown_option(options, &key));
+            }
+            matched
+        }
+        None => options.entries(),
+    };
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("name", DataType::Utf8, false),
+        Field::new("value", DataType::Utf8, true),
+        Field::new("description", DataType::Utf8, false),
+    ]));
+    let names = StringArray::from_iter_values(entries.iter().map(|e| &e.key));
+    let values = entries
+        .iter()
+        .map(|e| e.value.as_deref())
+        .collect::<StringArray>();
+    let descriptions = StringArray::from_iter_values(entries.iter().map(|e| e.description));
+    Ok(RecordBatch::try_new(
+        schema,
+        vec![Arc::new(names), Arc::new(values), Arc::new(descriptions)],
+    )?)
+}
+
+/// Find the full key of an option, e.g. `datafusion.execution.batch_size` for `batch_size`.
+fn resolve_key(options: &ConfigOptions, key: &str) -> Result<String, BackendError> {
+    let entries = options.entries();
+    if entries.iter().any(|e| e.key == key) {
+        return Ok(key.to_string());
+    }
+
+    let suffix = format!(".{}", key);
+    let matched = entries
+        .iter()
+        .filter(|e| e.key.ends_with(&suffix))
+        .collect::<Vec<_>>();
+    match matched.as_slice() {
+        [entry] => Ok(entry.key.clone()),
+        [] => Err(unknown_option(options, key)),
+        _ => Err(BackendError::UnknownOption {
+            key: key.to_string(),
+            suggestions: keys(matched),
+        }),
+    }
+}
+
+/// Suggest the options sharing the last part of the key, e.g. `pushdown` for `parquet.pushdown`.
+fn unknown_option(options: &ConfigOptions, key: &str) -> BackendError {
+    let name = key.rsplit('.').next().unwrap_or(key).to_lowercase();
+    let entries = options.entries();
+    let similar = entries
+        .iter()
+        .filter(|e| {
+            !name.is_empty() && e.key.rsplit('.').next().unwrap_or_default().contains(&name)
+        })
+        .collect::<Vec<_>>();
+    BackendError::UnknownOption {
+        key: key.to_string(),
+        suggestions: keys(similar),
+    }
+}
+
+fn keys(entries: Vec<&ConfigEntry>) -> Vec<String> {
+    entries.into_iter().map(|e| e.key.clone()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+
+    use arrow::array::AsArray;
+    use datafusion::config::{ConfigExtension, ExtensionOptions};
+    use datafusion::prelude::SessionConfig;
+
+    use super::*;
+
+    /// An extension repeating the batch size of DataFusion, so its short keys are ambiguous.
+    #[derive(Debug, Clone, Default)]
+    struct Shadow {
+        batch_size: Option<String>,
+    }
+
+    impl ConfigExtension for Shadow {
+        const PREFIX: &'static str = "shadow";
+    }
+
+    impl ExtensionOptions for Shadow {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn as_any_mut(&mut self) -> &mut dyn Any {
+            self
+        }
+
+        fn cloned(&self) -> Box<dyn ExtensionOptions> {
+            Box::new(self.clone())
+        }
+
+        fn set(&mut self, key: &str, value: &str) -> datafusion::error::Result<()> {
+            assert_eq!(key, "execution.batch_size");
+            self.batch_size = Some(value.to_string());
+            Ok(())
+        }
+
+        fn entries(&self) -> Vec<ConfigEntry> {
+            vec![ConfigEntry {
+                key: "shadow.execution.batch_size".to_string(),
+                value: self.batch_size.clone(),
+                description: "",
+            }]
+        }
+    }
+
+    fn options(batch: &RecordBatch) -> Vec<(String, Option<String>)> {
+        let names = batch.column(0).as_string::<i32>();
+        let values = batch.column(1).as_string::<i32>();
+        names
+            .iter()
+            .zip(values)
+            .map(|(name, value)| (name.unwrap().to_string(), value.map(str::to_string)))
+            .collect()
+    }
+
+    fn option(name: &str, value: &str) -> (String, Option<String>) {
+        (name.to_string(), Some(value.to_string()))
+    }
+
+    #[test]
+    fn resolve_key_should_complete_the_namespace() {
+        let options = ConfigOptions::new();
+        for key in [
+            "batch_size",
+            "execution.batch_size",
+            "datafusion.execution.batch_size",
+        ] {
+            assert_eq!(
+                resolve_key(&options, key).unwrap(),
+                "datafusion.execution.batch_size"
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_keys_should_suggest_similar_options() {
+        let options = ConfigOptions::new();
+        let err = resolve_key(&options, "parquet.pushdown").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown option: parquet.pushdown, did you mean one of: datafusion.execution.parquet.pushdown_filters"
+        );
+        let err = resolve_key(&options, "no_such_option").unwrap_err();
+        assert_eq!(err.to_string(), "Unknown option: no_such_option");
+        // only whole parts of the key are completed
+        assert!(resolve_key(&options, "ch_size").is_err());
+    }
+
+    #[test]
+    fn ambiguous_keys_should_list_the_candidates() {
+        let mut options = ConfigOptions::new();
+        options.extensions.insert(Shadow::default());
+        for key in ["batch_size", "execution.batch_size"] {
+            let err = resolve_key(&options, key).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!(
+                    "Unknown option: {}, did you mean one of: datafusion.execution.batch_size, shadow.execution.batch_size",
+                    key
+                )
+            );
+        }
+        assert_eq!(
+            resolve_key(&options, "shadow.execution.batch_size").unwrap(),
+            "shadow.execution.batch_size"
+        );
+    }
+
+    #[test]
+    fn set_option_should_be_shown() {
+        let ctx = SessionContext::new_with_config(
+            SessionConfig::new().with_option_extension(Shadow::default()),
+        );
+        assert!(matches!(
+            set_option(&ctx, "batch_size", "10"),
+            Err(BackendError::UnknownOption { .. })
+        ));
+        assert_eq!(
+            set_option(&ctx, "shadow.execution.batch_size", "10").unwrap(),
+            "shadow.execution.batch_size"
+        );
+        assert_eq!(
+            set_option(&ctx, "target_partitions", "3").unwrap(),
+            "datafusion.execution.target_partitions"
+        );
+
+        let shown = show_options(&ctx, Some("target_partitions")).unwrap();
+        assert_eq!(
+            options(&shown),
+            [option("datafusion.execution.target_partitions", "3")]
+        );
+        let shown = show_options(&ctx, Some("shadow")).unwrap();
+        assert_eq!(
+            options(&shown),
+            [option("shadow.execution.batch_size", "10")]
+        );
+        assert_eq!(ctx.state().config_options().execution.target_partitions, 3);
+        assert_eq!(ctx.state().config_options().execution.batch_size, 8192);
+    }
+
+    #[test]
+    fn invalid_values_should_keep_the_option() {
+        let ctx = SessionContext::new();
+        let err = set_option(&ctx, "batch_size", "many").unwrap_err();
+        assert!(matches!(
+            &err,
+            BackendError::InvalidOptionValue { key, value, .. }
+                if key == "datafusion.execution.batch_size" && value == "many"
+        ));
+        assert!(err
+            .to_string()
+            .starts_with("Invalid value many for datafusion.execution.batch_size: "));
+        let shown = show_options(&ctx, Some("batch_size")).unwrap();
+        assert_eq!(
+            options(&shown),
+            [option("datafusion.execution.batch_size", "8192")]
+        );
+
+        assert!(matches!(
+            set_option(&ctx, "no_such_option", "1"),
+            Err(BackendError::UnknownOption { .. })
+        ));
+        assert!(matches!(
+            show_options(&ctx, Some("no_such_option")),
+            Err(BackendError::UnknownOption { .. })
+        ));
+    }
+}
@@ -0,0 +1,202 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use arrow::array::{
+    ArrayRef, BinaryBuilder, BooleanBuilder, Date32Builder, Float32Builder, Float64Builder,
+    Int16Builder, Int32Builder, Int64Builder, RecordBatch, RecordBatchOptions, StringBuilder,
+    TimestampMicrosecondBuilder,
+};
+use arrow::datatypes::{DataType, Date32Type, SchemaRef, TimeUnit};
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use datafusion::error::{DataFusionError, Result as DFResult};
+use datafusion::prelude::SessionContext;
+use datafusion::sql::unparser::dialect::{Dialect, PostgreSqlDialect};
+use tokio_postgres::{types::Type, Client, NoTls, Row};
+
+use super::sql_table::{
+    quote_ident, quote_table, register_schema, SqlColumn, SqlExecutor, SqlTable,
+};
+use crate::backend::metadata::mask_password;
+
+/// Connect to the postgres database and register either the given table,
+/// or every table of the current schema under a schema named `name`.
+pub async fn register_postgres(
+    ctx: &SessionContext,
+    conn_str: &str,
+    name: &str,
+    table: Option<&str>,
+) -> anyhow::Result<()> {
+    let (client, connection) = tokio_postgres::connect(conn_str, NoTls)
+        .await
+        .with_context(|| format!("Failed to connect to postgres: {}", mask_password(conn_str)))?;
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            eprintln!("Postgres connection error: {}", e);
+        }
+    });
+    let executor = Arc::new(PostgresExecutor {
+        client,
+        dialect: PostgreSqlDialect {},
+    });
+
+    match table {
+        Some(table) => {
+            let provider = executor.clone().table(table).await?;
+            ctx.register_table(name, Arc::new(provider))?;
+        }
+        None => {
+            let rows = executor
+                .client
+                .query(
+                    "SELECT table_name::text FROM information_schema.tables \
+                     WHERE table_schema = current_schema() ORDER BY table_name",
+                    &[],
+                )
+                .await?;
+            let mut tables = vec![];
+            for row in rows {
+                let table: String = row.try_get(0)?;
+                let provider = executor.clone().table(&table).await?;
+                tables.push((table, provider));
+            }
+            register_schema(ctx, name, tables)?;
+        }
+    }
+    Ok(())
+}
+
+struct PostgresExecutor {
+    client: Client,
+    dialect: PostgreSqlDialect,
+}
+
+impl PostgresExecutor {
+    async fn table(self: Arc<Self>, table: &str) -> anyhow::Result<SqlTable> {
+        let stmt = self
+            .client
+            .prepare(&format!(
+                "SELECT * FROM {} LIMIT 0",
+                quote_table(&self.dialect, table)
+            ))
+            .await
+            .with_context(|| format!("Failed to load table {}", table))?;
+
+        let columns = stmt
+            .columns()
+            .iter()
+            .map(|c| {
+                let name = quote_ident(&self.dialect, c.name());
+                let (data_type, select, pushdown) = match *c.type_() {
+                    Type::BOOL => (DataType::Boolean, name, true),
+                    Type::INT2 => (DataType::Int16, name, true),
+                    Type::INT4 => (DataType::Int32, name, true),
+                    Type::INT8 => (DataType::Int64, name, true),
+                    Type::FLOAT4 => (DataType::Float32, name, true),
+                    Type::FLOAT8 => (DataType::Float64, name, true),
+                    // compared as exact decimals by postgres, as rounded floats by datafusion
+                    Type::NUMERIC => (DataType::Float64, format!("{}::float8", name), false),
+                    Type::TEXT | Type::VARCHAR | Type::NAME => (DataType::Utf8, name, true),
+                    // postgres ignores the trailing blanks of char(n) values in comparisons
+                    Type::BPCHAR => (DataType::Utf8, name, false),
+                    Type::BYTEA => (DataType::Binary, name, false),
+                    Type::DATE => (DataType::Date32, name, true),
+                    Type::TIMESTAMP => {
+                        (DataType::Timestamp(TimeUnit::Microsecond, None), name, true)
+                    }
+                    Type::TIMESTAMPTZ => (
+                        DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+                        name,
+                        true,
+                    ),
+                    // types without a native mapping (json, uuid, arrays...) are read as text
+                    _ => (DataType::Utf8, format!("{}::text", name), false),
+                };
+                SqlColumn {
+                    name: c.name().to_string(),
+                    data_type,
+                    select,
+                    pushdown,
+                }
+            })
+            .collect();
+
+        Ok(SqlTable::new(self, table, columns))
+    }
+}
+
+#[async_trait]
+impl SqlExecutor for PostgresExecutor {
+    fn dialect(&self) -> &dyn Dialect {
+        &self.dialect
+    }
+
+    async fn query(&self, sql: &str, schema: SchemaRef) -> DFResult<RecordBatch> {
+        let rows = self
+            .client
+            .query(sql, &[])
+            .await
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+
+        let arrays = schema
+            .fields()
+            .iter()
+            .enumerate()
+            .map(|(i, field)| build_array(&rows, i, field.data_type()))
+            .collect::<DFResult<Vec<_>>>()?;
+        let options = RecordBatchOptions::new().with_row_count(Some(rows.len()));
+        Ok(RecordBatch::try_new_with_options(schema, arrays, &options)?)
+    }
+}
+
+fn build_array(rows: &[Row], idx: usize, data_type: &DataType) -> DFResult<ArrayRef> {
+    macro_rules! build {
+        ($builder:ty, $native:ty) => {
+            build!($builder, $native, |v| v)
+        };
+        ($builder:ty, $native:ty, $map:expr) => {{
+            let mut builder = <$builder>::new();
+            for row in rows {
+                let value: Option<$native> = row
+                    .try_get(idx)
+                    .map_err(|e| DataFusionError::External(Box::new(e)))?;
+                builder.append_option(value.map($map));
+            }
+            builder.finish()
+        }};
+    }
+
+    let array: ArrayRef = match data_type {
+        DataType::Boolean => Arc::new(build!(BooleanBuilder, bool)),
+        DataType::Int16 => Arc::new(build!(Int16Builder, i16)),
+        DataType::Int32 => Arc::new(build!(Int32Builder, i32)),
+        DataType::Int64 => Arc::new(build!(Int64Builder, i64)),
+        DataType::Float32 => Arc::new(build!(Float32Builder, f32)),
+        DataType::Float64 => Arc::new(build!(Float64Builder, f64)),
+        DataType::Utf8 => Arc::new(build!(StringBuilder, &str)),
+        DataType::Binary => Arc::new(build!(BinaryBuilder, &[u8])),
+        DataType::Date32 => Arc::new(build!(
+            Date32Builder,
+            NaiveDate,
+            Date32Type::from_naive_date
+        )),
+        DataType::Timestamp(_, None) => {
+            Arc::new(build!(TimestampMicrosecondBuilder, NaiveDateTime, |t| {
+                t.and_utc().timestamp_micros()
+            }))
+        }
+        DataType::Timestamp(_, Some(tz)) => Arc::new(
+            build!(TimestampMicrosecondBuilder, DateTime<Utc>, |t| {
+                t.timestamp_micros()
+            })
+            .with_timezone(tz.clone()),
+        ),
+        other => {
+            return Err(DataFusionError::NotImplemented(format!(
+                "Reading postgres column as {}",
+                other
+            )))
+        }
+    };
+    Ok(array)
+}
@@ -0,0 +1,73 @@
+use std::fs::File;
+
+use arrow::array::{AsArray, RecordBatch, RecordBatchWriter};
+use arrow::datatypes::UInt64Type;
+use arrow::ipc::writer::{FileWriter, StreamWriter};
+use datafusion::config::{CsvOptions, JsonOptions};
+use datafusion::dataframe::{DataFrame, DataFrameWriteOptions};
+use datafusion::execution::SendableRecordBatchStream;
+use futures::StreamExt;
+
+use crate::cli::{SaveFile, SaveFormat};
+use crate::BackendError;
+
+/// Write the result of `df` into a single file, returning the number of rows written.
+pub async fn save_dataframe(df: DataFrame, file: &SaveFile) -> Result<usize, BackendError> {
+    let options = DataFrameWriteOptions::new().with_single_file_output(true);
+    let compression = *file.compression.get_variant();
+
+    let counts = match file.format {
+        SaveFormat::Parquet => df.write_parquet(&file.path, options, None).await?,
+        SaveFormat::Csv => {
+            let csv_opts = CsvOptions::default()
+                .with_has_header(true)
+                .with_compression(compression);
+            df.write_csv(&file.path, options, Some(csv_opts)).await?
+        }
+        SaveFormat::NdJson => {
+            let json_opts = JsonOptions {
+                compression,
+                ..Default::default()
+            };
+            df.write_json(&file.path, options, Some(json_opts)).await?
+        }
+        SaveFormat::Arrow | SaveFormat::ArrowStream => return save_arrow(df, file).await,
+    };
+
+    Ok(row_count(&counts))
+}
+
+/// DataFusion has no arrow writer for dataframes, so the batches are streamed into an IPC file.
+async fn save_arrow(df: DataFrame, file: &SaveFile) -> Result<usize, BackendError> {
+    let stream = df.execute_stream().await?;
+    let out = File::create(&file.path)?;
+    let schema = stream.schema();
+    match file.format {
+        SaveFormat::ArrowStream => {
+            write_batches(stream, StreamWriter::try_new(out, &schema)?).await
+        }
+        _ => write_batches(stream, FileWriter::try_new(out, &schema)?).await,
+    }
+}
+
+async fn write_batches(
+    mut stream: SendableRecordBatchStream,
+    mut writer: impl RecordBatchWriter,
+) -> Result<usize, BackendError> {
+    let mut rows = 0;
+    while let Some(batch) = stream.next().await {
+        let batch = batch?;
+        rows += batch.num_rows();
+        writer.write(&batch)?;
+    }
+    writer.close()?;
+    Ok(rows)
+}
+
+/// The write methods return a single `count` column with the number of rows written.
+fn row_count(batches: &[RecordBatch]) -> usize {
+    batches
+        .iter()
+        .flat_map(|b| b.column(0).as_primitive::<UInt64Type>().values().iter())
+        .sum::<u64>() as usize
+}
@@ -0,0 +1,376 @@
+use std::{any::Any, sync::Arc};
+
+use anyhow::Context;
+use arrow::array::RecordBatch;
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use async_trait::async_trait;
+use datafusion::catalog::schema::MemorySchemaProvider;
+use datafusion::common::ScalarValue;
+use datafusion::datasource::{TableProvider, TableType};
+use datafusion::error::Result as DFResult;
+use datafusion::execution::context::SessionState;
+use datafusion::logical_expr::expr_rewriter::unnormalize_col;
+use datafusion::logical_expr::{
+    Between, BinaryExpr, Expr, Like, Operator, TableProviderFilterPushDown,
+};
+use datafusion::physical_plan::{memory::MemoryExec, ExecutionPlan};
+use datafusion::prelude::SessionContext;
+use datafusion::sql::unparser::{dialect::Dialect, Unparser};
+use datafusion::sql::TableReference;
+
+/// A database connection that runs the `SELECT`s generated by [`SqlTable`].
+#[async_trait]
+pub trait SqlExecutor: Send + Sync {
+    fn dialect(&self) -> &dyn Dialect;
+
+    /// Run `sql` and read the returned rows as a batch of `schema`.
+    async fn query(&self, sql: &str, schema: SchemaRef) -> DFResult<RecordBatch>;
+}
+
+/// A column of a database table.
+pub struct SqlColumn {
+    pub name: String,
+    pub data_type: DataType,
+    /// The expression used in the select list, e.g. a cast to a readable type.
+    pub select: String,
+    /// Whether filters on this column are evaluated exactly like DataFusion does.
+    pub pushdown: bool,
+}
+
+/// A database table exposed to DataFusion.
+///
+/// Projections, limits and the filters that the database can evaluate exactly
+/// are pushed down into the generated `SELECT`, so only the needed rows
+/// and columns are transferred.
+pub struct SqlTable {
+    executor: Arc<dyn SqlExecutor>,
+    table: String,
+    columns: Vec<SqlColumn>,
+    schema: SchemaRef,
+}
+
+impl SqlTable {
+    /// `table` is either `table` or `schema.table`, unquoted.
+    pub fn new(executor: Arc<dyn SqlExecutor>, table: &str, columns: Vec<SqlColumn>) -> Self {
+        let table = quote_table(executor.dialect(), table);
+        let schema = Arc::new(Schema::new(
+            columns
+                .iter()
+                .map(|c| Field::new(&c.name, c.data_type.clone(), true))
+                .collect::<Vec<_>>(),
+        ));
+        Self {
+            executor,
+            table,
+            columns,
+            schema,
+        }
+    }
+
+    fn to_select_sql(
+        &self,
+        projection: &[usize],
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> DFResult<String> {
+        let columns = if projection.is_empty() {
+            "1".to_string()
+        } else {
+            projection
+                .iter()
+                .map(|&i| self.columns[i].select.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let mut sql = format!("SELECT {} FROM {}", columns, self.table);
+
+        let unparser = Unparser::new(self.executor.dialect());
+        let conditions = filters
+            .iter()
+            .map(|f| {
+                unparser
+                    .expr_to_sql(&unnormalize_col(f.clone()))
+                    .map(|e| format!("({})", e))
+            })
+            .collect::<DFResult<Vec<_>>>()?;
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+        if let Some(limit) = limit {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
+        Ok(sql)
+    }
+
+    /// Whether the database evaluates `expr` with the same semantics as DataFusion.
+    fn can_push_down(&self, expr: &Expr) -> bool {
+        match expr {
+            Expr::Column(c) => self
+                .columns
+                .iter()
+                .any(|col| col.name == c.name && col.pushdown),
+            Expr::Literal(v) => matches!(
+                v,
+                ScalarValue::Boolean(Some(_))
+                    | ScalarValue::Int8(Some(_))
+                    | ScalarValue::Int16(Some(_))
+                    | ScalarValue::Int32(Some(_))
+                    | ScalarValue::Int64(Some(_))
+                    | ScalarValue::UInt8(Some(_))
+                    | ScalarValue::UInt16(Some(_))
+                    | ScalarValue::UInt32(Some(_))
+                    | ScalarValue::UInt64(Some(_))
+                    | ScalarValue::Float32(Some(_))
+                    | ScalarValue::Float64(Some(_))
+                    | ScalarValue::Utf8(Some(_))
+            ),
+            Expr::BinaryExpr(BinaryExpr { left, op, right }) => {
+                let supported = match op {
+                    Operator::Eq | Operator::NotEq | Operator::And | Operator::Or => true,
+                    // string ordering depends on the database collation
+                    Operator::Lt | Operator::LtEq | Operator::Gt | Operator::GtEq => {
+                        !self.is_string(left) && !self.is_string(right)
+                    }
+                    // arithmetic may overflow, widen or round differently in the database
+                    _ => false,
+                };
+                supported && self.can_push_down(left) && self.can_push_down(right)
+            }
+            Expr::Not(e) | Expr::IsNull(e) | Expr::IsNotNull(e) => self.can_push_down(e),
+            Expr::Between(Between {
+                expr, low, high, ..
+            }) => self.can_push_down(expr) && self.can_push_down(low) && self.can_push_down(high),
+            Expr::InList(list) => {
+                self.can_push_down(&list.expr) && list.list.iter().all(|e| self.can_push_down(e))
+            }
+            Expr::Like(Like {
+                expr,
+                pattern,
+                escape_char: None,
+                case_insensitive: false,
+                ..
+            }) => self.can_push_down(expr) && self.can_push_down(pattern),
+            _ => false,
+        }
+    }
+
+    fn is_string(&self, expr: &Expr) -> bool {
+        match expr {
+            Expr::Column(c) => self
+                .columns
+                .iter()
+                .any(|col| col.name == c.name && col.data_type == DataType::Utf8),
+            Expr::Literal(v) => v.data_type() == DataType::Utf8,
+            _ => false,
+        }
+    }
+}
+
+#[async_trait]
+impl TableProvider for SqlTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    async fn scan(
+        &self,
+        _state: &SessionState,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> DFResult<Arc<dyn ExecutionPlan>> {
+        let projection = match projection {
+            Some(p) => p.clone(),
+            None => (0..self.columns.len()).collect(),
+        };
+        let schema = Arc::new(self.schema.project(&projection)?);
+        let sql = self.to_select_sql(&projection, filters, limit)?;
+        let batch = self.executor.query(&sql, schema.clone()).await?;
+
+        Ok(Arc::new(MemoryExec::try_new(&[vec![batch]], schema, None)?))
+    }
+
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> DFResult<Vec<TableProviderFilterPushDown>> {
+        Ok(filters
+            .iter()
+            .map(|f| {
+                if self.can_push_down(f) {
+                    TableProviderFilterPushDown::Exact
+                } else {
+                    TableProviderFilterPushDown::Unsupported
+                }
+            })
+            .collect())
+    }
+}
+
+/// Register every table of a database under a schema named `name`.
+pub fn register_schema(
+    ctx: &SessionContext,
+    name: &str,
+    tables: Vec<(String, impl TableProvider + 'static)>,
+) -> anyhow::Result<()> {
+    let catalog_name = ctx.state().config_options().catalog.default_catalog.clone();
+    let catalog = ctx
+        .catalog(&catalog_name)
+        .with_context(|| format!("Catalog {} not found", catalog_name))?;
+    catalog.register_schema(name, Arc::new(MemorySchemaProvider::new()))?;
+    for (table, provider) in tables {
+        ctx.register_table(TableReference::partial(name, table), Arc::new(provider))?;
+    }
+    Ok(())
+}
+
+/// Quote an identifier with the dialect's quote character.
+pub fn quote_ident(dialect: &dyn Dialect, ident: &str) -> String {
+    match dialect.identifier_quote_style(ident) {
+        Some(q) => format!("{q}{}{q}", ident.replace(q, &format!("{q}{q}"))),
+        None => ident.to_string(),
+    }
+}
+
+pub fn quote_table(dialect: &dyn Dialect, table: &str) -> String {
+    match table.split_once('.') {
+        Some((schema, table)) => format!(
+            "{}.{}",
+            quote_ident(dialect, schema),
+            quote_ident(dialect, table)
+        ),
+        None => quote_ident(dialect, table),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::prelude::{col, lit};
+    use datafusion::sql::unparser::dialect::PostgreSqlDialect;
+
+    use super::*;
+
+    struct StubExecutor(PostgreSqlDialect);
+
+    #[async_trait]
+    impl SqlExecutor for StubExecutor {
+        fn dialect(&self) -> &dyn Dialect {
+            &self.0
+        }
+
+        async fn query(&self, _sql: &str, schema: SchemaRef) -> DFResult<RecordBatch> {
+            Ok(RecordBatch::new_empty(schema))
+        }
+    }
+
+    fn column(name: &str, data_type: DataType, select: &str, pushdown: bool) -> SqlColumn {
+        SqlColumn {
+            name: name.to_string(),
+            data_type,
+            select: select.to_string(),
+            pushdown,
+        }
+    }
+
+    fn table(name: &str) -> SqlTable {
+        let columns = vec![
+            column("id", DataType::Int64, "\"id\"", true),
+            column("name", DataType::Utf8, "\"name\"", true),
+            column("price", DataType::Float64, "\"price\"::float8", false),
+            column("Created At", DataType::Date32, "\"Created At\"", true),
+        ];
+        SqlTable::new(Arc::new(StubExecutor(PostgreSqlDialect {})), name, columns)
+    }
+
+    #[test]
+    fn to_select_sql_should_select_projected_columns() {
+        let table = table("items");
+        assert_eq!(
+            table.to_select_sql(&[2, 0], &[], None).unwrap(),
+            "SELECT \"price\"::float8, \"id\" FROM \"items\""
+        );
+    }
+
+    #[test]
+    fn to_select_sql_should_select_1_for_empty_projection() {
+        let table = table("items");
+        assert_eq!(
+            table.to_select_sql(&[], &[], None).unwrap(),
+            "SELECT 1 FROM \"items\""
+        );
+    }
+
+    #[test]
+    fn to_select_sql_should_add_where_and_limit() {
+        let table = table("items");
+        let filters = [
+            col("id").gt(lit(1i64)),
+            col("name").eq(lit("a")).or(col("Created At").is_null()),
+        ];
+        assert_eq!(
+            table.to_select_sql(&[0, 1], &filters, Some(10)).unwrap(),
+            "SELECT \"id\", \"name\" FROM \"items\" \
+             WHERE ((\"id\" > 1)) AND (((\"name\" = 'a') OR \"Created At\" IS NULL)) LIMIT 10"
+        );
+    }
+
+    #[test]
+    fn to_select_sql_should_quote_identifiers() {
+        let table = table("my schema.odd\"name");
+        assert_eq!(
+            table.to_select_sql(&[0], &[], Some(1)).unwrap(),
+            "SELECT \"id\" FROM \"my schema\".\"odd\"\"name\" LIMIT 1"
+        );
+    }
+
+    #[test]
+    fn can_push_down_should_accept_exact_filters() {
+        let table = table("items");
+        let filters = [
+            col("id").eq(lit(1i64)),
+            col("id").gt_eq(lit(1i64)).and(col("id").lt(lit(5i64))),
+            col("name").not_eq(lit("a")),
+            col("name").is_not_null(),
+            col("id").between(lit(1i64), lit(5i64)),
+            col("id").in_list(vec![lit(1i64), lit(2i64)], false),
+            col("name").like(lit("a%")),
+            !col("Created At").is_null(),
+        ];
+        for filter in filters {
+            assert!(table.can_push_down(&filter), "{} is exact", filter);
+        }
+    }
+
+    #[test]
+    fn can_push_down_should_reject_inexact_filters() {
+        let table = table("items");
+        let filters = [
+            // not pushed down columns
+            col("price").gt(lit(1.5)),
+            col("price").is_null(),
+            // string ordering
+            col("name").lt(lit("b")),
+            // arithmetic
+            (col("id") + lit(1i64)).eq(lit(2i64)),
+            (col("id") - lit(1i64)).gt(lit(2i64)),
+            (col("id") * lit(2i64)).lt_eq(lit(2i64)),
+            // case insensitive and escaped patterns
+            col("name").ilike(lit("a%")),
+            // null literals and unknown columns
+            col("id").eq(lit(ScalarValue::Int64(None))),
+            col("missing").eq(lit(1i64)),
+        ];
+        for filter in filters {
+            assert!(!table.can_push_down(&filter), "{} is not exact", filter);
+        }
+    }
+}
@@ -0,0 +1,456 @@
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context;
+use arrow::array::{
+    ArrayRef, BinaryBuilder, BooleanBuilder, Float64Builder, Int64Builder, RecordBatch,
+    RecordBatchOptions, StringBuilder,
+};
+use arrow::datatypes::{DataType, SchemaRef};
+use async_trait::async_trait;
+use datafusion::error::{DataFusionError, Result as DFResult};
+use datafusion::prelude::SessionContext;
+use datafusion::sql::unparser::dialect::{Dialect, SqliteDialect};
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, OpenFlags};
+
+use super::sql_table::{
+    quote_ident, quote_table, register_schema, SqlColumn, SqlExecutor, SqlTable,
+};
+
+/// Open the sqlite file and register either the given table,
+/// or every table of the file under a schema named `name`.
+pub async fn register_sqlite(
+    ctx: &SessionContext,
+    path: &str,
+    name: &str,
+    table: Option<&str>,
+) -> anyhow::Result<()> {
+    let conn = Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .with_context(|| format!("Failed to open sqlite file: {}", path))?;
+    // keep `LIKE` case sensitive, as it is in DataFusion
+    conn.pragma_update(None, "case_sensitive_like", true)?;
+    let executor = Arc::new(SqliteExecutor {
+        conn: Arc::new(Mutex::new(conn)),
+        dialect: SqliteDialect {},
+    });
+
+    match table {
+        Some(table) => {
+            let provider = executor.clone().table(table)?;
+            ctx.register_table(name, Arc::new(provider))?;
+        }
+        None => {
+            let names = {
+                let conn = executor.conn.lock().unwrap();
+                let mut stmt = conn.prepare(
+                    "SELECT name FROM sqlite_master \
+                     WHERE type IN ('table', 'view') AND name NOT LIKE 'sqlite_%' ORDER BY name",
+                )?;
+                let names = stmt
+                    .query_map([], |row| row.get::<_, String>(0))?
+                    .collect::<Result<Vec<_>, _>>()?;
+                names
+            };
+
+            let mut tables = vec![];
+            for table in names {
+                let provider = executor.clone().table(&table)?;
+                tables.push((table, provider));
+            }
+            register_schema(ctx, name, tables)?;
+        }
+    }
+    Ok(())
+}
+
+struct SqliteExecutor {
+    conn: Arc<Mutex<Connection>>,
+    dialect: SqliteDialect,
+}
+
+impl SqliteExecutor {
+    fn table(self: Arc<Self>, table: &str) -> anyhow::Result<SqlTable> {
+        let columns = {
+            let conn = self.conn.lock().unwrap();
+            let stmt = conn
+                .prepare(&format!(
+                    "SELECT * FROM {} LIMIT 0",
+                    quote_table(&self.dialect, table)
+                ))
+                .with_context(|| format!("Failed to load table {}", table))?;
+            stmt.columns()
+                .iter()
+                .map(|c| {
+                    let (data_type, pushdown) = column_type(c.decl_type().unwrap_or_default());
+                    SqlColumn {
+                        name: c.name().to_string(),
+                        data_type,
+                        select: quote_ident(&self.dialect, c.name()),
+                        pushdown,
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+
+        Ok(SqlTable::new(self, table, columns))
+    }
+}
+
+/// Map a declared column type to arrow, following sqlite's type affinity rules.
+fn column_type(decl_type: &str) -> (DataType, bool) {
+    let decl_type = decl_type.to_uppercase();
+    if decl_type.contains("INT") {
+        (DataType::Int64, true)
+    } else if ["CHAR", "CLOB", "TEXT"]
+        .iter()
+        .any(|t| decl_type.contains(t))
+    {
+        (DataType::Utf8, true)
+    } else if decl_type.contains("BLOB") {
+        (DataType::Binary, false)
+    } else if ["REAL", "FLOA", "DOUB", "NUMERIC", "DECIMAL"]
+        .iter()
+        .any(|t| decl_type.contains(t))
+    {
+        (DataType::Float64, true)
+    } else if decl_type.contains("BOOL") {
+        (DataType::Boolean, true)
+    } else {
+        // dates and untyped columns may hold anything, so they are read as text
+        (DataType::Utf8, false)
+    }
+}
+
+#[async_trait]
+impl SqlExecutor for SqliteExecutor {
+    fn dialect(&self) -> &dyn Dialect {
+        &self.dialect
+    }
+
+    async fn query(&self, sql: &str, schema: SchemaRef) -> DFResult<RecordBatch> {
+        let conn = self.conn.clone();
+        let sql = sql.to_string();
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap();
+            let mut stmt = conn
+                .prepare(&sql)
+                .map_err(|e| DataFusionError::External(Box::new(e)))?;
+            let mut rows = stmt
+                .query([])
+                .map_err(|e| DataFusionError::External(Box::new(e)))?;
+
+            let mut builders = schema
+                .fields()
+                .iter()
+                .map(|f| ColumnBuilder::new(f.data_type()))
+                .collect::<DFResult<Vec<_>>>()?;
+            let mut num_rows = 0;
+            while let Some(row) = rows
+                .next()
+                .map_err(|e| DataFusionError::External(Box::new(e)))?
+            {
+                for (i, builder) in builders.iter_mut().enumerate() {
+                    let value = row
+                        .get_ref(i)
+                        .map_err(|e| DataFusionError::External(Box::new(e)))?;
+                    builder.append(value);
+                }
+                num_rows += 1;
+            }
+
+            let arrays = builders.into_iter().map(|b| b.finish()).collect();
+            let options = RecordBatchOptions::new().with_row_count(Some(num_rows));
+            Ok(RecordBatch::try_new_with_options(schema, arrays, &options)?)
+        })
+        .await
+        .map_err(|e| DataFusionError::External(Box::new(e)))?
+    }
+}
+
+/// Sqlite values are dynamically typed, so each value is converted
+/// to the declared column type where possible, one odd value does not fail the query.
+enum ColumnBuilder {
+    Int64(Int64Builder),
+    Float64(Float64Builder),
+    Boolean(BooleanBuilder),
+    Utf8(StringBuilder),
+    Binary(BinaryBuilder),
+}
+
+impl ColumnBuilder {
+    fn new(data_type: &DataType) -> DFResult<Self> {
+        Ok(match data_type {
+            DataType::Int64 => Self::Int64(Int64Builder::new()),
+            DataType::Float64 => Self::Float64(Float64Builder::new()),
+            DataType::Boolean => Self::Boolean(BooleanBuilder::new()),
+            DataType::Utf8 => Self::Utf8(StringBuilder::new()),
+            DataType::Binary => Self::Binary(BinaryBuilder::new()),
+            other => {
+                return Err(DataFusionError::NotImplemented(format!(
+                    "Reading sqlite column as {}",
+                    other
+                )))
+            }
+        })
+    }
+
+    /// Values of another type than their column are read as null, as the text columns
+    /// hold any value, numbers and blobs are read in their text form there.
+    fn append(&mut self, value: ValueRef) {
+        match (self, value) {
+            (Self::Int64(b), ValueRef::Integer(v)) => b.append_value(v),
+            (Self::Int64(b), ValueRef::Real(v)) if v.fract() == 0.0 => b.append_value(v as i64),
+            (Self::Int64(b), _) => b.append_null(),
+            (Self::Float64(b), ValueRef::Integer(v)) => b.append_value(v as f64),
+            (Self::Float64(b), ValueRef::Real(v)) => b.append_value(v),
+            (Self::Float64(b), _) => b.append_null(),
+            (Self::Boolean(b), ValueRef::Integer(v)) => b.append_value(v != 0),
+            (Self::Boolean(b), _) => b.append_null(),
+            (Self::Utf8(b), ValueRef::Null) => b.append_null(),
+            (Self::Utf8(b), ValueRef::Integer(v)) => b.append_value(v.to_string()),
+            (Self::Utf8(b), ValueRef::Real(v)) => b.append_value(v.to_string()),
+            (Self::Utf8(b), ValueRef::Text(v) | ValueRef::Blob(v)) => {
+                b.append_value(String::from_utf8_lossy(v))
+            }
+            (Self::Binary(b), ValueRef::Blob(v) | ValueRef::Text(v)) => b.append_value(v),
+            (Self::Binary(b), _) => b.append_null(),
+        }
+    }
+
+    fn finish(self) -> ArrayRef {
+        match self {
+            Self::Int64(mut b) => Arc::new(b.finish()),
+            Self::Float64(mut b) => Arc::new(b.finish()),
+            Self::Boolean(mut b) => Arc::new(b.finish()),
+            Self::Utf8(mut b) => Arc::new(b.finish()),
+            Self::Binary(mut b) => Arc::new(b.finish()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::{Array, AsArray, Int64Array};
+    use arrow::datatypes::Int64Type;
+
+    use super::*;
+
+    const SAMPLE: &str = "fixtures/sample.sqlite";
+
+    async fn count(ctx: &SessionContext, table: &str) -> i64 {
+        let batches = ctx
+            .sql(&format!("SELECT count(*) FROM {}", table))
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        batches[0].column(0).as_primitive::<Int64Type>().value(0)
+    }
+
+    #[tokio::test]
+    async fn register_sqlite_table_should_work() {
+        let ctx = SessionContext::new();
+        register_sqlite(&ctx, SAMPLE, "u", Some("users"))
+            .await
+            .unwrap();
+
+        let schema = ctx.table("u").await.unwrap().schema().as_arrow().clone();
+        let types = schema
+            .fields()
+            .iter()
+            .map(|f| (f.name().as_str(), f.data_type().clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            types,
+            [
+                ("id", DataType::Int64),
+                ("email", DataType::Utf8),
+                ("name", DataType::Utf8),
+                ("gender", DataType::Utf8),
+                ("created_at", DataType::Utf8),
+                ("finished_count", DataType::Int64),
+                ("score", DataType::Float64),
+                ("active", DataType::Boolean),
+            ]
+        );
+        assert_eq!(count(&ctx, "u").await, 100);
+    }
+
+    #[tokio::test]
+    async fn register_sqlite_file_should_register_every_table() {
+        let ctx = SessionContext::new();
+        register_sqlite(&ctx, SAMPLE, "db", None).await.unwrap();
+
+        let schema = ctx.catalog("datafusion").unwrap().schema("db").unwrap();
+        let mut tables = schema.table_names();
+        tables.sort();
+        assert_eq!(tables, ["players", "users"]);
+        assert_eq!(count(&ctx, "db.users").await, 100);
+        assert_eq!(count(&ctx, "db.players").await, 27);
+    }
+
+    #[tokio::test]
+    async fn register_sqlite_should_fail_on_unknown_table() {
+        let ctx = SessionContext::new();
+        let err = register_sqlite(&ctx, SAMPLE, "t", Some("missing"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Failed to load table missing");
+    }
+
+    #[tokio::test]
+    async fn select_should_push_down_filter_limit_and_projection() {
+        let ctx = SessionContext::new();
+        register_sqlite(&ctx, SAMPLE, "u", Some("users"))
+            .await
+            .unwrap();
+
+        let df = ctx
+            .sql("SELECT id, finished_count FROM u WHERE id > 10 AND active = true LIMIT 3")
+            .await
+            .unwrap();
+        let plan = df.clone().into_optimized_plan().unwrap();
+        assert_eq!(
+            plan.display_indent().to_string(),
+            "Limit: skip=0, fetch=3\n  \
+             TableScan: u projection=[id, finished_count], \
+             full_filters=[u.id > Int64(10), u.active], fetch=3"
+        );
+
+        let batches = df.collect().await.unwrap();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(
+            batches[0].column(0).as_primitive::<Int64Type>(),
+            &Int64Array::from(vec![12, 14, 16])
+        );
+        assert_eq!(
+            batches[0].column(1).as_primitive::<Int64Type>(),
+            &Int64Array::from(vec![4, 35, 28])
+        );
+    }
+
+    #[test]
+    fn column_type_should_follow_affinity_rules() {
+        let cases = [
+            ("INTEGER", DataType::Int64, true),
+            ("bigint", DataType::Int64, true),
+            ("UNSIGNED BIG INT", DataType::Int64, true),
+            ("VARCHAR(255)", DataType::Utf8, true),
+            ("NCHAR(10)", DataType::Utf8, true),
+            ("TEXT", DataType::Utf8, true),
+            ("CLOB", DataType::Utf8, true),
+            ("BLOB", DataType::Binary, false),
+            ("REAL", DataType::Float64, true),
+            ("DOUBLE PRECISION", DataType::Float64, true),
+            ("FLOAT", DataType::Float64, true),
+            ("NUMERIC", DataType::Float64, true),
+            ("DECIMAL(10,5)", DataType::Float64, true),
+            ("BOOLEAN", DataType::Boolean, true),
+            ("DATETIME", DataType::Utf8, false),
+            ("DATE", DataType::Utf8, false),
+            ("", DataType::Utf8, false),
+        ];
+        for (decl_type, data_type, pushdown) in cases {
+            assert_eq!(
+                column_type(decl_type),
+                (data_type, pushdown),
+                "declared type {}",
+                decl_type
+            );
+        }
+    }
+
+    #[test]
+    fn column_builder_should_convert_compatible_values() {
+        let mut ints = ColumnBuilder::new(&DataType::Int64).unwrap();
+        ints.append(ValueRef::Integer(1));
+        ints.append(ValueRef::Real(2.0));
+        ints.append(ValueRef::Null);
+        let ints = ints.finish();
+        assert_eq!(
+            ints.as_primitive::<Int64Type>(),
+            &Int64Array::from(vec![Some(1), Some(2), None])
+        );
+
+        let mut strings = ColumnBuilder::new(&DataType::Utf8).unwrap();
+        strings.append(ValueRef::Integer(1));
+        strings.append(ValueRef::Real(1.5));
+        strings.append(ValueRef::Text(b"a"));
+        strings.append(ValueRef::Blob(b"b\xff"));
+        let strings = strings.finish();
+        let strings = strings.as_string::<i32>();
+        assert_eq!(
+            strings.iter().collect::<Vec<_>>(),
+            [Some("1"), Some("1.5"), Some("a"), Some("b\u{fffd}")]
+        );
+    }
+
+    #[test]
+    fn column_builder_should_read_mismatched_values_as_null() {
+        let cases = [
+            (DataType::Int64, ValueRef::Real(1.5)),
+            (DataType::Int64, ValueRef::Text(b"1")),
+            (DataType::Float64, ValueRef::Blob(b"1")),
+            (DataType::Boolean, ValueRef::Text(b"true")),
+            (DataType::Binary, ValueRef::Integer(1)),
+        ];
+        for (data_type, value) in cases {
+            let mut builder = ColumnBuilder::new(&data_type).unwrap();
+            builder.append(value);
+            let array = builder.finish();
+            assert_eq!(
+                (array.len(), array.null_count()),
+                (1, 1),
+                "{:?} in {} column",
+                value,
+                data_type
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn mismatched_values_should_not_fail_the_query() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("loose.sqlite");
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE t (id INTEGER, score REAL, note TEXT);
+             INSERT INTO t VALUES (1, 1.5, 'a'), ('two', 'high', 3), (3, x'00', x'62');",
+        )
+        .unwrap();
+        drop(conn);
+
+        let ctx = SessionContext::new();
+        register_sqlite(&ctx, path.to_str().unwrap(), "t", Some("t"))
+            .await
+            .unwrap();
+        let batches = ctx
+            .sql("SELECT * FROM t")
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        assert_eq!(
+            arrow::util::pretty::pretty_format_batches(&batches)
+                .unwrap()
+                .to_string(),
+            "+----+-------+------+\n\
+             | id | score | note |\n\
+             +----+-------+------+\n\
+             | 1  | 1.5   | a    |\n\
+             |    |       | 3    |\n\
+             | 3  |       | b    |\n\
+             +----+-------+------+"
+        );
+    }
+
+    #[test]
+    fn column_builder_should_reject_unsupported_types() {
+        assert!(ColumnBuilder::new(&DataType::Date32).is_err());
+    }
+}
@@ -1,3 +1,42 @@
+mod avro;
+mod catalog;
+mod describe;
+mod excel;
 mod fusion;
+mod metadata;
+mod output;
+mod parquet_meta;
+mod polars;
+mod registry;
+mod session;
 
+use std::fmt;
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+pub use self::polars::PolarsBackend;
+pub use catalog::{Catalog, SharedCatalog};
+pub use describe::DescribeMethod;
 pub use fusion::DataFusionBackend;
+pub use registry::BackendRegistry;
+pub use session::default_session_file;
+
+/// The query engine that executes the commands.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Engine {
+    #[default]
+    #[value(name = "datafusion")]
+    DataFusion,
+    Polars,
+}
+
+impl fmt::Display for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DataFusion => write!(f, "datafusion"),
+            Self::Polars => write!(f, "polars"),
+        }
+    }
+}
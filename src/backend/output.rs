@@ -0,0 +1,132 @@
+use arrow::array::RecordBatch;
+use arrow::csv::WriterBuilder;
+use arrow::datatypes::SchemaRef;
+use arrow::error::ArrowError;
+use arrow::json::{ArrayWriter, LineDelimitedWriter};
+use arrow::util::display::{ArrayFormatter, FormatOptions};
+use arrow_cast::pretty::pretty_format_batches;
+
+use crate::cli::OutputFormat;
+
+/// Render the batches in the given output format.
+/// `schema` is used to print the header when there are no rows.
+pub fn format_batches(
+    schema: SchemaRef,
+    batches: &[RecordBatch],
+    format: OutputFormat,
+) -> Result<String, ArrowError> {
+    let empty = [RecordBatch::new_empty(schema)];
+    let batches = if batches.is_empty() { &empty } else { batches };
+
+    match format {
+        OutputFormat::Table => Ok(pretty_format_batches(batches)?.to_string()),
+        OutputFormat::Csv => format_csv(batches, b','),
+        OutputFormat::Tsv => format_csv(batches, b'\t'),
+        OutputFormat::Json => {
+            let mut writer = ArrayWriter::new(vec![]);
+            writer.write_batches(&batches.iter().collect::<Vec<_>>())?;
+            writer.finish()?;
+            // the writer outputs nothing at all without rows
+            let data = into_string(writer.into_inner())?;
+            Ok(if data.is_empty() {
+                "[]".to_string()
+            } else {
+                data
+            })
+        }
+        OutputFormat::NdJson => {
+            let mut writer = LineDelimitedWriter::new(vec![]);
+            writer.write_batches(&batches.iter().collect::<Vec<_>>())?;
+            writer.finish()?;
+            into_string(writer.into_inner())
+        }
+        OutputFormat::Markdown => format_markdown(batches),
+        OutputFormat::Vertical => format_vertical(batches),
+    }
+}
+
+fn format_csv(batches: &[RecordBatch], delimiter: u8) -> Result<String, ArrowError> {
+    let mut writer = WriterBuilder::new()
+        .with_header(true)
+        .with_delimiter(delimiter)
+        .build(vec![]);
+    for batch in batches {
+        writer.write(batch)?;
+    }
+    into_string(writer.into_inner())
+}
+
+fn format_markdown(batches: &[RecordBatch]) -> Result<String, ArrowError> {
+    let schema = batches[0].schema();
+    let names = schema
+        .fields()
+        .iter()
+        .map(|f| escape_markdown(f.name()))
+        .collect::<Vec<_>>();
+
+    let mut lines = vec![
+        format!("| {} |", names.join(" | ")),
+        format!("|{}", "---|".repeat(names.len())),
+    ];
+    for batch in batches {
+        let formatters = formatters(batch)?;
+        for row in 0..batch.num_rows() {
+            let cells = formatters
+                .iter()
+                .map(|f| escape_markdown(&f.value(row).to_string()))
+                .collect::<Vec<_>>();
+            lines.push(format!("| {} |", cells.join(" | ")));
+        }
+    }
+    Ok(lines.join("\n"))
+}
+
+/// Print each record as a block of `column | value` lines, like `\x` in psql.
+fn format_vertical(batches: &[RecordBatch]) -> Result<String, ArrowError> {
+    let schema = batches[0].schema();
+    let width = schema
+        .fields()
+        .iter()
+        .map(|f| f.name().chars().count())
+        .max()
+        .unwrap_or_default();
+
+    let mut lines = vec![];
+    let mut record = 0;
+    for batch in batches {
+        let formatters = formatters(batch)?;
+        for row in 0..batch.num_rows() {
+            record += 1;
+            lines.push(format!("-[ RECORD {} ]", record));
+            for (field, formatter) in schema.fields().iter().zip(&formatters) {
+                lines.push(format!(
+                    "{:width$} | {}",
+                    field.name(),
+                    formatter.value(row),
+                    width = width
+                ));
+            }
+        }
+    }
+    if lines.is_empty() {
+        lines.push("(0 rows)".to_string());
+    }
+    Ok(lines.join("\n"))
+}
+
+fn formatters(batch: &RecordBatch) -> Result<Vec<ArrayFormatter<'_>>, ArrowError> {
+    batch
+        .columns()
+        .iter()
+        .map(|c| ArrayFormatter::try_new(c.as_ref(), &FormatOptions::default()))
+        .collect()
+}
+
+fn escape_markdown(s: &str) -> String {
+    s.replace('|', "\\|").replace('\n', " ")
+}
+
+fn into_string(buf: Vec<u8>) -> Result<String, ArrowError> {
+    let s = String::from_utf8(buf).map_err(|e| ArrowError::ExternalError(Box::new(e)))?;
+    Ok(s.trim_end().to_string())
+}
@@ -0,0 +1,410 @@
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use arrow::{
+    array::{ArrayRef, BooleanArray, Int64Array, RecordBatch, StringArray, UInt64Array},
+    datatypes::{Decimal128Type, DecimalType, DECIMAL128_MAX_PRECISION},
+};
+use chrono::{DateTime, NaiveDate};
+use parquet::{
+    basic::{ConvertedType, LogicalType, TimeUnit},
+    data_type::ByteArray,
+    file::{
+        footer::parse_metadata,
+        metadata::{ColumnChunkMetaData, ParquetMetaData, RowGroupMetaData},
+        statistics::Statistics,
+    },
+    schema::types::ColumnDescriptor,
+};
+
+use crate::{cli::OutputFormat, BackendError, ReplDisplay};
+
+/// Longer statistics and metadata values are cut, e.g. the arrow schema kept in the metadata.
+const MAX_VALUE_LEN: usize = 64;
+
+/// The footer of a parquet file, read into one batch per section.
+pub struct ParquetMeta {
+    path: PathBuf,
+    rows: i64,
+    version: i32,
+    created_by: Option<String>,
+    schema: RecordBatch,
+    key_values: RecordBatch,
+    row_groups: RecordBatch,
+    column_chunks: RecordBatch,
+}
+
+impl ParquetMeta {
+    /// Only the footer is read, not the pages.
+    pub fn read(path: &Path) -> Result<Self, BackendError> {
+        let metadata = parse_metadata(&File::open(path)?)?;
+        let file = metadata.file_metadata();
+        Ok(Self {
+            path: path.to_path_buf(),
+            rows: file.num_rows(),
+            version: file.version(),
+            created_by: file.created_by().map(str::to_string),
+            schema: schema_batch(&metadata)?,
+            key_values: key_values_batch(&metadata)?,
+            row_groups: row_groups_batch(&metadata)?,
+            column_chunks: column_chunks_batch(&metadata)?,
+        })
+    }
+}
+
+impl ReplDisplay for ParquetMeta {
+    async fn display(self, format: OutputFormat) -> Result<String, BackendError> {
+        let mut out = format!(
+            "File: {}\nRows: {}, row groups: {}, version: {}, created by: {}\n",
+            self.path.display(),
+            self.rows,
+            self.row_groups.num_rows(),
+            self.version,
+            self.created_by.as_deref().unwrap_or("unknown")
+        );
+        let sections = [
+            ("Schema", self.schema),
+            ("Key-value metadata", self.key_values),
+            ("Row groups", self.row_groups),
+            ("Column chunks", self.column_chunks),
+        ];
+        for (title, batch) in sections {
+            out.push_str(&format!("\n{}:\n", title));
+            if batch.num_rows() == 0 {
+                out.push_str("none\n");
+            } else {
+                out.push_str(&batch.display(format).await?);
+                out.push('\n');
+            }
+        }
+        Ok(out.trim_end().to_string())
+    }
+}
+
+fn schema_batch(metadata: &ParquetMetaData) -> Result<RecordBatch, BackendError> {
+    let columns = metadata.file_metadata().schema_descr().columns();
+    let names = columns.iter().map(|c| c.path().string());
+    let physical = columns.iter().map(|c| c.physical_type().to_string());
+    let logical = columns.iter().map(|c| logical_type(c));
+    let repetition = columns
+        .iter()
+        .map(|c| c.self_type().get_basic_info().repetition().to_string());
+    Ok(RecordBatch::try_from_iter(vec![
+        ("column", strings(names.map(Some))),
+        ("physical_type", strings(physical.map(Some))),
+        ("logical_type", strings(logical)),
+        ("repetition", strings(repetition.map(Some))),
+    ])?)
+}
+
+fn key_values_batch(metadata: &ParquetMetaData) -> Result<RecordBatch, BackendError> {
+    let key_values = metadata
+        .file_metadata()
+        .key_value_metadata()
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let keys = key_values.iter().map(|kv| Some(kv.key.clone()));
+    let values = key_values
+        .iter()
+        .map(|kv| kv.value.as_deref().map(truncate));
+    Ok(RecordBatch::try_from_iter(vec![
+        ("key", strings(keys)),
+        ("value", strings(values)),
+    ])?)
+}
+
+fn row_groups_batch(metadata: &ParquetMetaData) -> Result<RecordBatch, BackendError> {
+    let row_groups = metadata.row_groups();
+    let int64 = |f: fn(&RowGroupMetaData) -> i64| -> ArrayRef {
+        Arc::new(row_groups.iter().map(f).collect::<Int64Array>())
+    };
+    Ok(RecordBatch::try_from_iter(vec![
+        ("row_group", row_group_ids(row_groups.len(), 1)),
+        ("rows", int64(|rg| rg.num_rows())),
+        ("columns", int64(|rg| rg.num_columns() as i64)),
+        ("uncompressed_bytes", int64(|rg| rg.total_byte_size())),
+        ("compressed_bytes", int64(|rg| rg.compressed_size())),
+    ])?)
+}
+
+fn column_chunks_batch(metadata: &ParquetMetaData) -> Result<RecordBatch, BackendError> {
+    let row_groups = metadata.row_groups();
+    let chunks = row_groups
+        .iter()
+        .flat_map(|rg| rg.columns())
+        .collect::<Vec<_>>();
+    let stats = chunks
+        .iter()
+        .map(|c| c.statistics().and_then(|s| min_max(s, c.column_descr())))
+        .collect::<Vec<_>>();
+
+    let columns = chunks.iter().map(|c| Some(c.column_path().string()));
+    let encodings = chunks.iter().map(|c| {
+        let encodings = c.encodings().iter().map(|e| e.to_string());
+        Some(encodings.collect::<Vec<_>>().join(", "))
+    });
+    let compression = chunks.iter().map(|c| Some(c.compression().to_string()));
+    let null_count = chunks
+        .iter()
+        .map(|c| c.statistics().map(|s| s.null_count()))
+        .collect::<UInt64Array>();
+    let bytes = |f: fn(&&ColumnChunkMetaData) -> i64| -> ArrayRef {
+        Arc::new(chunks.iter().map(f).collect::<Int64Array>())
+    };
+    let present = |f: fn(&&ColumnChunkMetaData) -> bool| -> ArrayRef {
+        Arc::new(chunks.iter().map(|c| Some(f(c))).collect::<BooleanArray>())
+    };
+    let row_group = row_group_ids(
+        row_groups.len(),
+        row_groups.first().map_or(0, |rg| rg.num_columns()),
+    );
+    Ok(RecordBatch::try_from_iter(vec![
+        ("row_group", row_group),
+        ("column", strings(columns)),
+        ("encodings", strings(encodings)),
+        ("compression", strings(compression)),
+        ("min", strings(stats.iter().map(|s| s.clone().map(|s| s.0)))),
+        ("max", strings(stats.into_iter().map(|s| s.map(|s| s.1)))),
+        ("null_count", Arc::new(null_count)),
+        ("uncompressed_bytes", bytes(|c| c.uncompressed_size())),
+        ("compressed_bytes", bytes(|c| c.compressed_size())),
+        (
+            "bloom_filter",
+            present(|c| c.bloom_filter_offset().is_some()),
+        ),
+        (
+            "column_index",
+            present(|c| c.column_index_offset().is_some()),
+        ),
+        (
+            "offset_index",
+            present(|c| c.offset_index_offset().is_some()),
+        ),
+    ])?)
+}
+
+/// The index of the row group of every row, for rows repeated `per_group` times.
+fn row_group_ids(row_groups: usize, per_group: usize) -> ArrayRef {
+    let ids = (0..row_groups as u64).flat_map(|i| std::iter::repeat_n(i, per_group));
+    Arc::new(ids.collect::<UInt64Array>())
+}
+
+fn strings(values: impl Iterator<Item = Option<String>>) -> ArrayRef {
+    Arc::new(values.collect::<StringArray>())
+}
+
+/// The converted type where there is one, e.g. `UTF8` or `TIMESTAMP_MICROS`.
+fn logical_type(column: &ColumnDescriptor) -> Option<String> {
+    match column.converted_type() {
+        ConvertedType::NONE => column.logical_type().map(|t| format!("{:?}", t)),
+        converted => Some(converted.to_string()),
+    }
+}
+
+/// The min and max statistics, read with the logical type of the column where it changes
+/// how they are shown: decimals, dates, timestamps and strings.
+fn min_max(stats: &Statistics, column: &ColumnDescriptor) -> Option<(String, String)> {
+    if !stats.has_min_max_set() {
+        return None;
+    }
+    if column.converted_type() == ConvertedType::DECIMAL {
+        let scale = column.type_scale();
+        match stats {
+            Statistics::Int32(s) => {
+                return Some(pair(s.min(), s.max(), |v| decimal(*v as i128, scale)))
+            }
+            Statistics::Int64(s) => {
+                return Some(pair(s.min(), s.max(), |v| decimal(*v as i128, scale)))
+            }
+            Statistics::ByteArray(s) => {
+                return Some(pair(s.min(), s.max(), |v| decimal_bytes(v.data(), scale)))
+            }
+            Statistics::FixedLenByteArray(s) => {
+                return Some(pair(s.min(), s.max(), |v| decimal_bytes(v.data(), scale)))
+            }
+            _ => {}
+        }
+    }
+    let logical = column.logical_type();
+    Some(match stats {
+        Statistics::Boolean(s) => pair(s.min(), s.max(), bool::to_string),
+        Statistics::Int32(s) if logical == Some(LogicalType::Date) => {
+            pair(s.min(), s.max(), |days| {
+                NaiveDate::from_num_days_from_ce_opt(*days + UNIX_EPOCH_DAYS)
+                    .map_or(days.to_string(), |d| d.to_string())
+            })
+        }
+        Statistics::Int32(s) => pair(s.min(), s.max(), i32::to_string),
+        Statistics::Int64(s) => match logical {
+            Some(LogicalType::Timestamp { unit, .. }) => {
+                pair(s.min(), s.max(), |v| timestamp(*v, &unit))
+            }
+            _ => pair(s.min(), s.max(), i64::to_string),
+        },
+        Statistics::Int96(s) => pair(s.min(), s.max(), |v| v.to_string()),
+        Statistics::Float(s) => pair(s.min(), s.max(), f32::to_string),
+        Statistics::Double(s) => pair(s.min(), s.max(), f64::to_string),
+        Statistics::ByteArray(s) => pair(s.min(), s.max(), bytes_value),
+        Statistics::FixedLenByteArray(s) => pair(s.min(), s.max(), |v| bytes_value(v)),
+    })
+}
+
+/// Days from 0001-01-01 to 1970-01-01, parquet dates count from the latter.
+const UNIX_EPOCH_DAYS: i32 = 719_163;
+
+fn pair<T>(min: &T, max: &T, f: impl Fn(&T) -> String) -> (String, String) {
+    (f(min), f(max))
+}
+
+fn timestamp(value: i64, unit: &TimeUnit) -> String {
+    let dt = match unit {
+        TimeUnit::MILLIS(_) => DateTime::from_timestamp_millis(value),
+        TimeUnit::MICROS(_) => DateTime::from_timestamp_micros(value),
+        TimeUnit::NANOS(_) => Some(DateTime::from_timestamp_nanos(value)),
+    };
+    dt.map_or(value.to_string(), |dt| dt.naive_utc().to_string())
+}
+
+/// An unscaled decimal value, e.g. `12345` of scale 2 is `123.45`.
+fn decimal(unscaled: i128, scale: i32) -> String {
+    Decimal128Type::format_decimal(unscaled, DECIMAL128_MAX_PRECISION, scale as i8)
+}
+
+/// Decimals longer than 16 bytes are shown raw, in hex.
+fn decimal_bytes(bytes: &[u8], scale: i32) -> String {
+    if bytes.is_empty() || bytes.len() > 16 {
+        return format!("raw 0x{}", hex(bytes));
+    }
+    // big-endian two's complement, sign extended to 16 bytes
+    let fill = if bytes[0] & 0x80 == 0 { 0 } else { 0xff };
+    let mut buf = [fill; 16];
+    buf[16 - bytes.len()..].copy_from_slice(bytes);
+    decimal(i128::from_be_bytes(buf), scale)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Strings as they are, binary values in hex.
+fn bytes_value(value: &ByteArray) -> String {
+    match value.as_utf8() {
+        Ok(s) => truncate(s),
+        Err(_) => truncate(&hex(value.data())),
+    }
+}
+
+fn truncate(s: &str) -> String {
+    match s.char_indices().nth(MAX_VALUE_LEN) {
+        Some((i, _)) => format!("{}...", &s[..i]),
+        None => s.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::AsArray;
+    use parquet::{
+        data_type::FixedLenByteArray,
+        schema::{parser::parse_message_type, types::SchemaDescriptor},
+    };
+
+    use super::*;
+
+    fn columns() -> SchemaDescriptor {
+        let schema = "message m {
+            required int32 day (DATE);
+            required int64 at (TIMESTAMP(MICROS, true));
+            required int32 price (DECIMAL(9, 2));
+            required fixed_len_byte_array(3) amount (DECIMAL(6, 3));
+        }";
+        SchemaDescriptor::new(Arc::new(parse_message_type(schema).unwrap()))
+    }
+
+    fn strings(batch: &RecordBatch, column: &str) -> Vec<String> {
+        let array = batch.column_by_name(column).unwrap().as_string::<i32>();
+        array
+            .iter()
+            .map(|v| v.unwrap_or_default().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn read_should_describe_the_footer() {
+        let meta = ParquetMeta::read(Path::new("fixtures/sample.parquet")).unwrap();
+        assert_eq!(meta.rows, 1000);
+        assert_eq!(meta.row_groups.num_rows(), 1);
+        assert_eq!(meta.schema.num_rows(), 13);
+        assert_eq!(strings(&meta.schema, "column")[..2], ["email", "name"]);
+        assert_eq!(strings(&meta.schema, "logical_type")[3], "TIMESTAMP_MICROS");
+
+        let chunks = &meta.column_chunks;
+        assert_eq!(strings(chunks, "column")[3], "created_at");
+        assert_eq!(strings(chunks, "compression")[3], "LZ4_RAW");
+        assert_eq!(strings(chunks, "min")[3], "2019-05-09 17:59:25.896589");
+        assert_eq!(strings(chunks, "max")[3], "2024-02-05 03:50:25.903207");
+    }
+
+    #[test]
+    fn min_max_should_decode_dates_and_timestamps() {
+        let columns = columns();
+        // 2024-08-29 is 19964 days after 1970-01-01
+        let days = Statistics::int32(Some(0), Some(19964), None, 0, false);
+        assert_eq!(
+            min_max(&days, &columns.column(0)),
+            Some(("1970-01-01".to_string(), "2024-08-29".to_string()))
+        );
+        let micros = Statistics::int64(Some(-1), Some(1_724_889_600_000_001), None, 0, false);
+        assert_eq!(
+            min_max(&micros, &columns.column(1)),
+            Some((
+                "1969-12-31 23:59:59.999999".to_string(),
+                "2024-08-29 00:00:00.000001".to_string()
+            ))
+        );
+        let unset = Statistics::int32(None, None, None, 3, false);
+        assert_eq!(min_max(&unset, &columns.column(0)), None);
+    }
+
+    #[test]
+    fn min_max_should_scale_decimals() {
+        let columns = columns();
+        let prices = Statistics::int32(Some(-5), Some(12345), None, 0, false);
+        assert_eq!(
+            min_max(&prices, &columns.column(2)),
+            Some(("-0.05".to_string(), "123.45".to_string()))
+        );
+        let bytes = |b: &[u8]| Some(FixedLenByteArray::from(b.to_vec()));
+        let amounts = Statistics::fixed_len_byte_array(
+            bytes(&[0xff, 0xfc, 0x18]),
+            bytes(&[0x01, 0xe2, 0x40]),
+            None,
+            0,
+            false,
+        );
+        assert_eq!(
+            min_max(&amounts, &columns.column(3)),
+            Some(("-1.000".to_string(), "123.456".to_string()))
+        );
+    }
+
+    #[test]
+    fn row_group_ids_should_repeat_per_column() {
+        let ids = row_group_ids(2, 3);
+        let ids = ids.as_any().downcast_ref::<UInt64Array>().unwrap();
+        assert_eq!(ids.values(), &[0, 0, 0, 1, 1, 1]);
+    }
+
+    #[test]
+    fn truncate_should_cut_long_values_on_chars() {
+        assert_eq!(truncate("short"), "short");
+        let long = "名".repeat(MAX_VALUE_LEN + 1);
+        assert_eq!(
+            truncate(&long),
+            format!("{}...", "名".repeat(MAX_VALUE_LEN))
+        );
+    }
+}
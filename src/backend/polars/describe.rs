@@ -0,0 +1,203 @@
+use polars::prelude::*;
+
+use crate::backend::describe::{DescribeMethod, Described};
+
+/// Compute every statistic of every column with a single select,
+/// so the dataset is only scanned once.
+pub fn describe_frame(
+    mut lf: LazyFrame,
+    methods: &[DescribeMethod],
+) -> PolarsResult<Described<DataFrame>> {
+    let schema = lf.schema()?;
+
+    let mut exprs = vec![];
+    for (i, (name, data_type)) in schema.iter().enumerate() {
+        for method in methods {
+            if supports(*method, data_type) {
+                exprs.push(stat_expr(*method, name).alias(&method.alias(i)));
+            }
+        }
+    }
+
+    let stats = if exprs.is_empty() {
+        None
+    } else {
+        Some(lf.select(exprs).collect()?)
+    };
+
+    let (values, others): (Vec<_>, Vec<_>) = methods.iter().partition(|m| m.is_value());
+    let table = |methods: &[DescribeMethod], own_type| {
+        (!methods.is_empty())
+            .then(|| describe_table(&schema, methods, own_type, stats.as_ref()))
+            .transpose()
+    };
+    Ok(Described {
+        statistics: table(&others, false)?,
+        values: table(&values, true)?,
+    })
+}
+
+/// The statistics of the columns as a table with one row per method, in the type of
+/// the column or as floats.
+fn describe_table(
+    schema: &Schema,
+    methods: &[DescribeMethod],
+    own_type: bool,
+    stats: Option<&DataFrame>,
+) -> PolarsResult<DataFrame> {
+    let mut columns = vec![Series::new(
+        "describe",
+        methods.iter().map(|m| m.name()).collect::<Vec<_>>(),
+    )];
+    for (i, (name, data_type)) in schema.iter().enumerate() {
+        // the values of nested columns are not computed, nor written in every format
+        if own_type && !methods.iter().any(|m| supports(*m, data_type)) {
+            continue;
+        }
+        let data_type = if own_type {
+            data_type.clone()
+        } else {
+            DataType::Float64
+        };
+        let mut values = Series::new_empty(name, &data_type);
+        for method in methods {
+            let value = match stats.and_then(|s| s.column(&method.alias(i)).ok()) {
+                Some(column) => column.cast(&data_type)?,
+                None => Series::full_null(name, 1, &data_type),
+            };
+            values.append(&value)?;
+        }
+        columns.push(values);
+    }
+    DataFrame::new(columns)
+}
+
+fn supports(method: DescribeMethod, data_type: &DataType) -> bool {
+    match method {
+        DescribeMethod::Count | DescribeMethod::NullCount => true,
+        DescribeMethod::Mean
+        | DescribeMethod::Std
+        | DescribeMethod::Median
+        | DescribeMethod::Percentile(_) => data_type.is_numeric(),
+        DescribeMethod::Min | DescribeMethod::Max => {
+            data_type.is_numeric() || data_type.is_temporal() || data_type == &DataType::String
+        }
+        DescribeMethod::ApproxDistinct
+        | DescribeMethod::Distinct
+        | DescribeMethod::Top
+        | DescribeMethod::TopCount => !data_type.is_nested(),
+        DescribeMethod::MinLength | DescribeMethod::AvgLength | DescribeMethod::MaxLength => {
+            data_type == &DataType::String
+        }
+        DescribeMethod::TrueCount | DescribeMethod::FalseCount => data_type == &DataType::Boolean,
+    }
+}
+
+fn stat_expr(method: DescribeMethod, name: &str) -> Expr {
+    let column = col(name);
+    match method {
+        DescribeMethod::Count => column.count(),
+        DescribeMethod::NullCount => column.null_count(),
+        DescribeMethod::Mean => column.mean(),
+        DescribeMethod::Std => column.std(1),
+        DescribeMethod::Min => column.min(),
+        DescribeMethod::Max => column.max(),
+        DescribeMethod::Median => column.median(),
+        DescribeMethod::Percentile(p) => {
+            column.quantile(lit(p as f64 / 100.0), QuantileInterpolOptions::Linear)
+        }
+        DescribeMethod::ApproxDistinct => column.drop_nulls().approx_n_unique(),
+        DescribeMethod::Distinct => column.drop_nulls().n_unique(),
+        DescribeMethod::Top => top_value(column),
+        DescribeMethod::TopCount => column.clone().eq(top_value(column)).sum(),
+        DescribeMethod::MinLength => column.str().len_chars().min(),
+        DescribeMethod::AvgLength => column.str().len_chars().mean(),
+        DescribeMethod::MaxLength => column.str().len_chars().max(),
+        DescribeMethod::TrueCount => column.sum(),
+        DescribeMethod::FalseCount => column.not().sum(),
+    }
+}
+
+/// The most frequent value, nulls left out and the smallest one on a tie.
+fn top_value(column: Expr) -> Expr {
+    column.drop_nulls().mode().sort(Default::default()).first()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::{cli::OutputFormat, ReplDisplay};
+
+    #[tokio::test]
+    async fn describe_should_compute_the_statistics_of_every_column() {
+        let df = df!(
+            "id" => [Some(4i64), Some(1), None, Some(3), Some(2)],
+            "name" => [Some("bb"), Some("a"), Some("bb"), None, Some("a")],
+            "active" => [Some(true), Some(false), Some(true), Some(true), None],
+        )
+        .unwrap();
+        let methods = [
+            DescribeMethod::Count,
+            DescribeMethod::NullCount,
+            DescribeMethod::Min,
+            DescribeMethod::Max,
+            DescribeMethod::Percentile(50),
+            DescribeMethod::Distinct,
+            DescribeMethod::Top,
+            DescribeMethod::TopCount,
+            DescribeMethod::MinLength,
+            DescribeMethod::MaxLength,
+            DescribeMethod::TrueCount,
+            DescribeMethod::FalseCount,
+        ];
+        let df = describe_frame(df.lazy(), &methods).unwrap();
+        assert_eq!(
+            df.display(OutputFormat::Csv).await.unwrap(),
+            "\
+describe,id,name,active
+count,4.0,4.0,4.0
+null_count,1.0,1.0,1.0
+p50,2.5,,
+distinct,4.0,2.0,2.0
+top_count,1.0,2.0,3.0
+min_length,,1.0,
+max_length,,2.0,
+true_count,,,3.0
+false_count,,,1.0
+
+describe,id,name,active
+min,1,a,
+max,4,bb,
+top,1,a,true"
+        );
+    }
+
+    #[tokio::test]
+    async fn describe_should_keep_values_in_their_type() {
+        let df = df!(
+            "id" => [9_007_199_254_740_993i64, 9_007_199_254_740_993, 1],
+            "day" => [
+                NaiveDate::from_ymd_opt(2024, 8, 29).unwrap(),
+                NaiveDate::from_ymd_opt(2024, 8, 30).unwrap(),
+                NaiveDate::from_ymd_opt(2024, 8, 30).unwrap(),
+            ],
+        )
+        .unwrap();
+        let methods = [
+            DescribeMethod::Min,
+            DescribeMethod::Max,
+            DescribeMethod::Top,
+        ];
+        let described = describe_frame(df.lazy(), &methods).unwrap();
+        assert_eq!(
+            described.display(OutputFormat::Csv).await.unwrap(),
+            "\
+describe,id,day
+min,1,2024-08-29
+max,9007199254740993,2024-08-30
+top,9007199254740993,2024-08-30"
+        );
+    }
+}
@@ -0,0 +1,410 @@
+mod describe;
+mod save;
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Cursor;
+use std::num::NonZeroUsize;
+use std::path::Path;
+
+use arrow::array::RecordBatch;
+use arrow::datatypes::{DataType as ArrowDataType, TimeUnit as ArrowTimeUnit};
+use arrow::ipc::{reader::FileReader, writer::FileWriter};
+use polars::io::HiveOptions;
+use polars::prelude::*;
+use polars::sql::SQLContext;
+
+use crate::{
+    backend::{
+        avro::read_avro, excel::read_workbook, output::format_batches, Catalog, DescribeMethod,
+    },
+    cli::{ConnectOpts, DatasetConn, FileOpts, IpcFormat, OutputFormat, ReadOpts, SaveFile},
+    Backend, BackendError, ReplDisplay,
+};
+
+use self::{describe::describe_frame, save::save_frame};
+
+/// The functions supported by polars SQL, which does not expose them.
+const SQL_FUNCTIONS: &[&str] = &[
+    "abs",
+    "acos",
+    "acosd",
+    "array_agg",
+    "array_contains",
+    "array_get",
+    "array_length",
+    "array_lower",
+    "array_mean",
+    "array_reverse",
+    "array_sum",
+    "array_to_string",
+    "array_unique",
+    "array_upper",
+    "asin",
+    "asind",
+    "atan",
+    "atan2",
+    "atan2d",
+    "atand",
+    "avg",
+    "bit_length",
+    "cbrt",
+    "ceil",
+    "ceiling",
+    "char_length",
+    "character_length",
+    "coalesce",
+    "concat",
+    "concat_ws",
+    "cos",
+    "cosd",
+    "cot",
+    "cotd",
+    "count",
+    "date",
+    "date_part",
+    "degrees",
+    "div",
+    "ends_with",
+    "exp",
+    "first",
+    "floor",
+    "greatest",
+    "if",
+    "ifnull",
+    "initcap",
+    "last",
+    "least",
+    "left",
+    "length",
+    "ln",
+    "log",
+    "log10",
+    "log1p",
+    "log2",
+    "lower",
+    "ltrim",
+    "max",
+    "median",
+    "min",
+    "mod",
+    "nullif",
+    "octet_length",
+    "pi",
+    "pow",
+    "power",
+    "radians",
+    "regexp_like",
+    "replace",
+    "reverse",
+    "right",
+    "round",
+    "rtrim",
+    "sign",
+    "sin",
+    "sind",
+    "sqrt",
+    "starts_with",
+    "stddev",
+    "stddev_samp",
+    "strftime",
+    "strpos",
+    "strptime",
+    "substr",
+    "sum",
+    "tan",
+    "tand",
+    "time",
+    "timestamp",
+    "unnest",
+    "upper",
+    "var",
+    "var_samp",
+    "variance",
+];
+
+/// A backend running the commands on Polars lazy frames, registered by dataset name.
+#[derive(Default)]
+pub struct PolarsBackend {
+    tables: BTreeMap<String, LazyFrame>,
+}
+
+impl PolarsBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn table(&self, name: &str) -> Result<LazyFrame, BackendError> {
+        self.tables
+            .get(name)
+            .cloned()
+            .ok_or_else(|| BackendError::DatasetNotFound(name.to_string()))
+    }
+
+    /// Polars SQL context holding every registered dataset.
+    fn sql_context(&self) -> SQLContext {
+        let mut ctx = SQLContext::new();
+        for (name, lf) in &self.tables {
+            ctx.register(name, lf.clone());
+        }
+        ctx
+    }
+}
+
+impl Backend for PolarsBackend {
+    async fn connect(&mut self, opts: &ConnectOpts) -> Result<(), BackendError> {
+        let lf = match opts.dataset()? {
+            DatasetConn::Csv(file_opts) => {
+                if file_opts.compression.is_compressed() {
+                    return Err(BackendError::Unsupported(
+                        "compressed csv files with the polars engine".to_string(),
+                    ));
+                }
+                let read = &opts.read;
+                if read.escape.is_some() {
+                    return Err(BackendError::Unsupported(
+                        "--escape with the polars engine".to_string(),
+                    ));
+                }
+                let comment = read.comment.map(|c| (c as char).to_string());
+                let mut reader = LazyCsvReader::new(unpartitioned(&file_opts, "csv")?)
+                    .with_has_header(!read.no_header)
+                    .with_separator(read.delimiter.unwrap_or(b','))
+                    .with_quote_char(Some(read.quote.unwrap_or(b'"')))
+                    .with_comment_prefix(comment.as_deref())
+                    .with_null_values(read.null.clone().map(NullValues::AllColumnsSingle))
+                    .with_schema(read_schema(read)?);
+                if let Some(rows) = read.infer_rows {
+                    reader = reader.with_infer_schema_length(Some(rows));
+                }
+                reader.finish()?
+            }
+            DatasetConn::Parquet(file_opts) => {
+                let mut args = ScanArgsParquet::default();
+                if !file_opts.partitions.is_empty() {
+                    args.hive_options = HiveOptions {
+                        enabled: Some(true),
+                        schema: Some(Arc::new(polars_schema(
+                            &file_opts.partitions,
+                            "partitions",
+                        )?)),
+                        ..Default::default()
+                    };
+                }
+                LazyFrame::scan_parquet(scan_path(&file_opts), args)?
+            }
+            DatasetConn::NdJson(file_opts) => {
+                if file_opts.compression.is_compressed() {
+                    return Err(BackendError::Unsupported(
+                        "compressed json files with the polars engine".to_string(),
+                    ));
+                }
+                let mut reader = LazyJsonLineReader::new(unpartitioned(&file_opts, "json")?)
+                    .with_schema(read_schema(&opts.read)?);
+                if let Some(rows) = opts.read.infer_rows {
+                    reader = reader.with_infer_schema_length(NonZeroUsize::new(rows));
+                }
+                reader.finish()?
+            }
+            DatasetConn::Arrow(file_opts, IpcFormat::File) => {
+                LazyFrame::scan_ipc(unpartitioned(&file_opts, "arrow")?, ScanArgsIpc::default())?
+            }
+            DatasetConn::Arrow(file_opts, IpcFormat::Stream) => {
+                let path = file_opts.single_file("arrow streams")?;
+                IpcStreamReader::new(File::open(path)?).finish()?.lazy()
+            }
+            DatasetConn::Avro(file_opts) => read_avro(&file_opts)?.lazy(),
+            DatasetConn::Excel(file_opts) => {
+                from_record_batch(&read_workbook(&file_opts, &opts.read)?)?.lazy()
+            }
+            DatasetConn::Postgres(_) | DatasetConn::MySql(_) | DatasetConn::Sqlite(_) => {
+                return Err(BackendError::Unsupported(
+                    "databases with the polars engine".to_string(),
+                ))
+            }
+        };
+        self.tables.insert(opts.name.clone(), lf);
+        Ok(())
+    }
+
+    async fn disconnect(&mut self, name: &str) -> Result<(), BackendError> {
+        self.tables
+            .remove(name)
+            .map(|_| ())
+            .ok_or_else(|| BackendError::DatasetNotFound(name.to_string()))
+    }
+
+    async fn rename(&mut self, from: &str, to: &str) -> Result<(), BackendError> {
+        let lf = self
+            .tables
+            .remove(from)
+            .ok_or_else(|| BackendError::DatasetNotFound(from.to_string()))?;
+        self.tables.insert(to.to_string(), lf);
+        Ok(())
+    }
+
+    async fn tables(&self) -> Result<Vec<String>, BackendError> {
+        Ok(self.tables.keys().cloned().collect())
+    }
+
+    async fn schema(&self, name: &str) -> Result<impl ReplDisplay, BackendError> {
+        let schema = self.table(name)?.schema()?;
+        let (names, types): (Vec<_>, Vec<_>) = schema
+            .iter()
+            .map(|(name, data_type)| (name.to_string(), data_type.to_string()))
+            .unzip();
+        // polars does not track nullability, every column may hold nulls
+        let nullable = vec!["YES"; names.len()];
+        let df = df!("column_name" => names, "data_type" => types, "is_nullable" => nullable)?;
+        Ok(df)
+    }
+
+    async fn describe(
+        &self,
+        name: &str,
+        methods: &[DescribeMethod],
+    ) -> Result<impl ReplDisplay, BackendError> {
+        let df = describe_frame(self.table(name)?, methods)?;
+        Ok(df)
+    }
+
+    async fn head(&self, name: &str, size: usize) -> Result<impl ReplDisplay, BackendError> {
+        let lf = self.table(name)?.limit(size as IdxSize);
+        Ok(lf)
+    }
+
+    async fn sql(&self, sql: &str) -> Result<impl ReplDisplay, BackendError> {
+        let lf = self.sql_context().execute(sql)?;
+        Ok(lf)
+    }
+
+    async fn save(&self, sql: &str, file: &SaveFile) -> Result<usize, BackendError> {
+        let df = self.sql_context().execute(sql)?.collect()?;
+        save_frame(df, file)
+    }
+
+    async fn catalog(&self) -> Result<Catalog, BackendError> {
+        let mut datasets = BTreeMap::new();
+        for (name, lf) in &self.tables {
+            let columns = lf
+                .clone()
+                .schema()?
+                .iter_names()
+                .map(|n| n.to_string())
+                .collect();
+            datasets.insert(name.clone(), columns);
+        }
+        let functions = SQL_FUNCTIONS.iter().map(|f| f.to_string()).collect();
+        Ok(Catalog {
+            datasets,
+            functions,
+        })
+    }
+
+    async fn set_option(&mut self, _key: &str, _value: &str) -> Result<String, BackendError> {
+        Err(BackendError::Unsupported(
+            "session options with the polars engine".to_string(),
+        ))
+    }
+
+    async fn show_options(&self, _key: Option<&str>) -> Result<impl ReplDisplay, BackendError> {
+        Err::<DataFrame, _>(BackendError::Unsupported(
+            "session options with the polars engine".to_string(),
+        ))
+    }
+}
+
+impl ReplDisplay for DataFrame {
+    async fn display(self, format: OutputFormat) -> Result<String, BackendError> {
+        let (schema, batches) = to_record_batches(self)?;
+        Ok(format_batches(schema, &batches, format)?)
+    }
+}
+
+impl ReplDisplay for LazyFrame {
+    async fn display(self, format: OutputFormat) -> Result<String, BackendError> {
+        self.collect()?.display(format).await
+    }
+}
+
+/// Polars reads the files of a directory through a glob, so files like `_SUCCESS` are left out.
+fn scan_path(file_opts: &FileOpts) -> String {
+    if Path::new(&file_opts.filename).is_dir() {
+        let dir = file_opts.filename.trim_end_matches('/');
+        return format!("{}/**/*.{}", dir, file_opts.ext);
+    }
+    file_opts.filename.clone()
+}
+
+/// Polars only reads Hive partitions of parquet files.
+fn unpartitioned(file_opts: &FileOpts, format: &str) -> Result<String, BackendError> {
+    if !file_opts.partitions.is_empty() {
+        return Err(BackendError::Unsupported(format!(
+            "partitioned {} files with the polars engine",
+            format
+        )));
+    }
+    Ok(scan_path(file_opts))
+}
+
+/// The file schema given with `--schema`.
+fn read_schema(read: &ReadOpts) -> Result<Option<SchemaRef>, BackendError> {
+    read.schema
+        .as_ref()
+        .map(|schema| Ok(Arc::new(polars_schema(&schema.columns, "columns")?)))
+        .transpose()
+}
+
+/// Convert the arrow types of `--schema` and Hive partitions, named `what` in errors.
+fn polars_schema(columns: &[(String, ArrowDataType)], what: &str) -> Result<Schema, BackendError> {
+    columns
+        .iter()
+        .map(|(name, data_type)| {
+            let data_type = match data_type {
+                ArrowDataType::Boolean => DataType::Boolean,
+                ArrowDataType::Int8 => DataType::Int8,
+                ArrowDataType::Int16 => DataType::Int16,
+                ArrowDataType::Int32 => DataType::Int32,
+                ArrowDataType::Int64 => DataType::Int64,
+                ArrowDataType::Float32 => DataType::Float32,
+                ArrowDataType::Float64 => DataType::Float64,
+                ArrowDataType::Date32 => DataType::Date,
+                ArrowDataType::Timestamp(ArrowTimeUnit::Nanosecond, None) => {
+                    DataType::Datetime(TimeUnit::Nanoseconds, None)
+                }
+                ArrowDataType::Utf8 => DataType::String,
+                other => {
+                    return Err(BackendError::Unsupported(format!(
+                        "{} {} with the polars engine",
+                        other, what
+                    )))
+                }
+            };
+            Ok(Field::new(name, data_type))
+        })
+        .collect()
+}
+
+/// Convert an arrow record batch to a polars frame through the IPC format.
+fn from_record_batch(batch: &RecordBatch) -> Result<DataFrame, BackendError> {
+    let mut buf = vec![];
+    let mut writer = FileWriter::try_new(&mut buf, &batch.schema())?;
+    writer.write(batch)?;
+    writer.finish()?;
+    drop(writer);
+    Ok(IpcReader::new(Cursor::new(buf)).finish()?)
+}
+
+/// Convert a polars frame to arrow record batches through the IPC format,
+/// so both engines share the same output.
+pub(crate) fn to_record_batches(
+    mut df: DataFrame,
+) -> Result<(arrow::datatypes::SchemaRef, Vec<RecordBatch>), BackendError> {
+    let mut buf = vec![];
+    IpcWriter::new(&mut buf)
+        .with_pl_flavor(false)
+        .finish(&mut df)?;
+    let reader = FileReader::try_new(Cursor::new(buf), None)?;
+    let schema = reader.schema();
+    Ok((schema, reader.collect::<Result<Vec<_>, _>>()?))
+}
@@ -0,0 +1,36 @@
+use std::fs::File;
+
+use polars::prelude::*;
+
+use crate::cli::{SaveFile, SaveFormat};
+use crate::BackendError;
+
+/// Write the frame into a single file, returning the number of rows written.
+pub fn save_frame(mut df: DataFrame, file: &SaveFile) -> Result<usize, BackendError> {
+    if file.compression.is_compressed() {
+        return Err(BackendError::Unsupported(
+            "compressed output files with the polars engine".to_string(),
+        ));
+    }
+
+    let writer = File::create(&file.path)?;
+    match file.format {
+        SaveFormat::Parquet => {
+            ParquetWriter::new(writer).finish(&mut df)?;
+        }
+        SaveFormat::Csv => CsvWriter::new(writer)
+            .include_header(true)
+            .finish(&mut df)?,
+        SaveFormat::NdJson => JsonWriter::new(writer)
+            .with_json_format(JsonFormat::JsonLines)
+            .finish(&mut df)?,
+        SaveFormat::Arrow => IpcWriter::new(writer)
+            .with_pl_flavor(false)
+            .finish(&mut df)?,
+        SaveFormat::ArrowStream => IpcStreamWriter::new(writer)
+            .with_pl_flavor(false)
+            .finish(&mut df)?,
+    }
+
+    Ok(df.height())
+}
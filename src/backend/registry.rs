@@ -0,0 +1,738 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use arrow::array::RecordBatch;
+
+use crate::{
+    cli::{ConnectOpts, DatasetConn, OutputFormat, SaveFile},
+    Backend, BackendError, DataFusionConfig, ReplDisplay, ReplOptions,
+};
+
+use super::{
+    metadata::{data_files, list_datasets, DatasetMeta},
+    parquet_meta::ParquetMeta,
+    session::{read_session, write_session},
+    Catalog, DataFusionBackend, DescribeMethod, Engine, PolarsBackend,
+};
+
+/// Holds the active backend and the datasets connected so far,
+/// so they can be registered again when the engine is switched.
+pub struct BackendRegistry {
+    active: ActiveBackend,
+    connected: Vec<DatasetMeta>,
+    format: OutputFormat,
+    /// The session file kept up to date with the connected datasets.
+    session: Option<PathBuf>,
+    head_size: usize,
+    datafusion: DataFusionConfig,
+    /// The options changed with `set`, applied again when switching back to DataFusion.
+    options: Vec<(String, String)>,
+}
+
+enum ActiveBackend {
+    DataFusion(DataFusionBackend),
+    Polars(PolarsBackend),
+}
+
+/// The output of whichever backend is active.
+enum BackendOutput<A, B> {
+    DataFusion(A),
+    Polars(B),
+}
+
+impl BackendRegistry {
+    pub fn new(opts: ReplOptions) -> Self {
+        Self {
+            active: ActiveBackend::new(opts.engine, &opts.datafusion),
+            connected: vec![],
+            format: opts.format,
+            session: opts.session,
+            head_size: opts.head_size,
+            datafusion: opts.datafusion,
+            options: vec![],
+        }
+    }
+
+    pub fn head_size(&self) -> usize {
+        self.head_size
+    }
+
+    pub fn format(&self) -> OutputFormat {
+        self.format
+    }
+
+    pub fn set_format(&mut self, format: OutputFormat) {
+        self.format = format;
+    }
+
+    pub fn engine(&self) -> Engine {
+        match self.active {
+            ActiveBackend::DataFusion(_) => Engine::DataFusion,
+            ActiveBackend::Polars(_) => Engine::Polars,
+        }
+    }
+
+    /// Replace the active backend with a new `engine` and register the connected datasets on it.
+    /// Returns the datasets the new engine failed to register, they are kept
+    /// so switching back registers them again.
+    pub async fn switch(&mut self, engine: Engine) -> Vec<(String, BackendError)> {
+        let mut active = ActiveBackend::new(engine, &self.datafusion);
+        if let ActiveBackend::DataFusion(backend) = &mut active {
+            for (key, value) in &self.options {
+                // they were valid when set on the previous DataFusion backend
+                let _ = backend.set_option(key, value).await;
+            }
+        }
+        let mut failed = vec![];
+        for dataset in &self.connected {
+            if let Err(e) = active.connect(&dataset.opts).await {
+                failed.push((dataset.opts.name.clone(), e));
+            }
+        }
+        self.active = active;
+        failed
+    }
+
+    /// Write the connected datasets to a session file, returns how many were written.
+    pub fn save_session(&self, path: &Path) -> Result<usize, BackendError> {
+        write_session(path, &self.connected_opts())?;
+        Ok(self.connected.len())
+    }
+
+    /// Connect the datasets of a session file.
+    /// Returns the names of the connected datasets and the ones that failed.
+    pub async fn load_session(
+        &mut self,
+        path: &Path,
+    ) -> Result<(Vec<String>, Vec<(String, BackendError)>), BackendError> {
+        let mut connected = vec![];
+        let mut failed = vec![];
+        for opts in read_session(path)? {
+            match self.connect(&opts).await {
+                Ok(()) => connected.push(opts.name),
+                Err(e) => failed.push((opts.name, e)),
+            }
+        }
+        Ok((connected, failed))
+    }
+
+    /// The registered tables, with the source, format, files and time of connection of their dataset.
+    pub async fn list(&self) -> Result<RecordBatch, BackendError> {
+        let mut tables = self.tables().await?;
+        tables.sort();
+        list_datasets(&tables, &self.connected)
+    }
+
+    /// The footers of the files of a connected parquet dataset, or of a parquet path.
+    pub fn parquet_meta(&self, target: &str) -> Result<Vec<ParquetMeta>, BackendError> {
+        let dataset = match self.position(target) {
+            Ok(i) => self.connected[i].opts.dataset()?,
+            Err(_) => DatasetConn::resolve(target, None)?,
+        };
+        match dataset {
+            DatasetConn::Parquet(file_opts) => data_files(&file_opts)?
+                .iter()
+                .map(|f| ParquetMeta::read(f))
+                .collect(),
+            dataset => Err(BackendError::Unsupported(format!(
+                "parquet-meta of {} datasets",
+                dataset.format_name()
+            ))),
+        }
+    }
+
+    /// Connect a dataset again, so files changed on disk get their schema inferred again.
+    /// The dataset is left as it was if it fails to connect.
+    pub async fn refresh(&mut self, name: &str) -> Result<(), BackendError> {
+        let i = self.position(name)?;
+        let opts = self.connected[i].opts.clone();
+        let dataset = opts.dataset()?;
+
+        // the tables are put aside until the new ones are registered
+        let aside = format!("__refresh_{}", name);
+        let registered = match self.active.rename(name, &aside).await {
+            Ok(()) => true,
+            // the active engine failed to register it when switched to
+            Err(BackendError::DatasetNotFound(_)) => false,
+            Err(e) => return Err(e),
+        };
+        if let Err(e) = self.active.connect(&opts).await {
+            if registered {
+                self.active.rename(&aside, name).await?;
+            }
+            return Err(e);
+        }
+        if registered {
+            self.active.disconnect(&aside).await?;
+        }
+        self.connected[i] = DatasetMeta::new(&opts, &dataset);
+        Ok(())
+    }
+
+    fn position(&self, name: &str) -> Result<usize, BackendError> {
+        self.connected
+            .iter()
+            .position(|d| d.opts.name == name)
+            .ok_or_else(|| BackendError::DatasetNotFound(name.to_string()))
+    }
+
+    fn connected_opts(&self) -> Vec<ConnectOpts> {
+        self.connected.iter().map(|d| d.opts.clone()).collect()
+    }
+
+    /// Record the connected datasets in the session file, if there is one.
+    fn update_session(&self) -> Result<(), BackendError> {
+        match &self.session {
+            Some(session) => write_session(session, &self.connected_opts()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl ActiveBackend {
+    fn new(engine: Engine, datafusion: &DataFusionConfig) -> Self {
+        match engine {
+            Engine::DataFusion => Self::DataFusion(DataFusionBackend::new(datafusion)),
+            Engine::Polars => Self::Polars(PolarsBackend::new()),
+        }
+    }
+
+    async fn connect(&mut self, opts: &ConnectOpts) -> Result<(), BackendError> {
+        match self {
+            Self::DataFusion(backend) => backend.connect(opts).await,
+            Self::Polars(backend) => backend.connect(opts).await,
+        }
+    }
+
+    async fn disconnect(&mut self, name: &str) -> Result<(), BackendError> {
+        match self {
+            Self::DataFusion(backend) => backend.disconnect(name).await,
+            Self::Polars(backend) => backend.disconnect(name).await,
+        }
+    }
+
+    async fn rename(&mut self, from: &str, to: &str) -> Result<(), BackendError> {
+        match self {
+            Self::DataFusion(backend) => backend.rename(from, to).await,
+            Self::Polars(backend) => backend.rename(from, to).await,
+        }
+    }
+}
+
+impl Backend for BackendRegistry {
+    async fn connect(&mut self, opts: &ConnectOpts) -> Result<(), BackendError> {
+        if self.position(&opts.name).is_ok() {
+            return Err(BackendError::DatasetExists(opts.name.clone()));
+        }
+        let dataset = opts.dataset()?;
+        self.active.connect(opts).await?;
+        // the files are only looked at once the engine registered them
+        self.connected.push(DatasetMeta::new(opts, &dataset));
+        self.update_session()
+    }
+
+    async fn disconnect(&mut self, name: &str) -> Result<(), BackendError> {
+        let i = self.position(name)?;
+        match self.active.disconnect(name).await {
+            // the active engine failed to register it when switched to
+            Ok(()) | Err(BackendError::DatasetNotFound(_)) => {}
+            Err(e) => return Err(e),
+        }
+        self.connected.remove(i);
+        self.update_session()
+    }
+
+    async fn rename(&mut self, from: &str, to: &str) -> Result<(), BackendError> {
+        let i = self.position(from)?;
+        if self.position(to).is_ok() {
+            return Err(BackendError::DatasetExists(to.to_string()));
+        }
+        match self.active.rename(from, to).await {
+            Ok(()) | Err(BackendError::DatasetNotFound(_)) => {}
+            Err(e) => return Err(e),
+        }
+        self.connected[i].opts.name = to.to_string();
+        self.update_session()
+    }
+
+    async fn tables(&self) -> Result<Vec<String>, BackendError> {
+        match &self.active {
+            ActiveBackend::DataFusion(backend) => backend.tables().await,
+            ActiveBackend::Polars(backend) => backend.tables().await,
+        }
+    }
+
+    async fn schema(&self, name: &str) -> Result<impl ReplDisplay, BackendError> {
+        Ok(match &self.active {
+            ActiveBackend::DataFusion(backend) => {
+                BackendOutput::DataFusion(backend.schema(name).await?)
+            }
+            ActiveBackend::Polars(backend) => BackendOutput::Polars(backend.schema(name).await?),
+        })
+    }
+
+    async fn describe(
+        &self,
+        name: &str,
+        methods: &[DescribeMethod],
+    ) -> Result<impl ReplDisplay, BackendError> {
+        Ok(match &self.active {
+            ActiveBackend::DataFusion(backend) => {
+                BackendOutput::DataFusion(backend.describe(name, methods).await?)
+            }
+            ActiveBackend::Polars(backend) => {
+                BackendOutput::Polars(backend.describe(name, methods).await?)
+            }
+        })
+    }
+
+    async fn head(&self, name: &str, size: usize) -> Result<impl ReplDisplay, BackendError> {
+        Ok(match &self.active {
+            ActiveBackend::DataFusion(backend) => {
+                BackendOutput::DataFusion(backend.head(name, size).await?)
+            }
+            ActiveBackend::Polars(backend) => {
+                BackendOutput::Polars(backend.head(name, size).await?)
+            }
+        })
+    }
+
+    async fn sql(&self, sql: &str) -> Result<impl ReplDisplay, BackendError> {
+        Ok(match &self.active {
+            ActiveBackend::DataFusion(backend) => {
+                BackendOutput::DataFusion(backend.sql(sql).await?)
+            }
+            ActiveBackend::Polars(backend) => BackendOutput::Polars(backend.sql(sql).await?),
+        })
+    }
+
+    /// The file is written under a staged name, so a failed save leaves no partial file behind.
+    async fn save(&self, sql: &str, file: &SaveFile) -> Result<usize, BackendError> {
+        let staged = file.staged();
+        let result = match &self.active {
+            ActiveBackend::DataFusion(backend) => backend.save(sql, &staged).await,
+            ActiveBackend::Polars(backend) => backend.save(sql, &staged).await,
+        }
+        .and_then(|rows| Ok(fs::rename(&staged.path, &file.path).map(|_| rows)?));
+        if result.is_err() {
+            let _ = fs::remove_file(&staged.path);
+        }
+        result
+    }
+
+    async fn catalog(&self) -> Result<Catalog, BackendError> {
+        match &self.active {
+            ActiveBackend::DataFusion(backend) => backend.catalog().await,
+            ActiveBackend::Polars(backend) => backend.catalog().await,
+        }
+    }
+
+    async fn set_option(&mut self, key: &str, value: &str) -> Result<String, BackendError> {
+        let key = match &mut self.active {
+            ActiveBackend::DataFusion(backend) => backend.set_option(key, value).await?,
+            ActiveBackend::Polars(backend) => backend.set_option(key, value).await?,
+        };
+        self.options.retain(|(k, _)| *k != key);
+        self.options.push((key.clone(), value.to_string()));
+        Ok(key)
+    }
+
+    async fn show_options(&self, key: Option<&str>) -> Result<impl ReplDisplay, BackendError> {
+        Ok(match &self.active {
+            ActiveBackend::DataFusion(backend) => {
+                BackendOutput::DataFusion(backend.show_options(key).await?)
+            }
+            ActiveBackend::Polars(backend) => {
+                BackendOutput::Polars(backend.show_options(key).await?)
+            }
+        })
+    }
+}
+
+impl<A: ReplDisplay, B: ReplDisplay> ReplDisplay for BackendOutput<A, B> {
+    async fn display(self, format: OutputFormat) -> Result<String, BackendError> {
+        match self {
+            Self::DataFusion(output) => output.display(format).await,
+            Self::Polars(output) => output.display(format).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::datasource::file_format::file_compression_type::FileCompressionType;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::cli::SaveFormat;
+
+    async fn registry(engine: Engine) -> BackendRegistry {
+        let mut registry = BackendRegistry::new(ReplOptions {
+            engine,
+            ..Default::default()
+        });
+        let opts = ConnectOpts::new(
+            "fixtures/juventus.csv".to_string(),
+            None,
+            "j".to_string(),
+            None,
+        );
+        registry.connect(&opts).await.unwrap();
+        registry
+    }
+
+    fn csv_file(dir: &TempDir) -> SaveFile {
+        SaveFile {
+            path: dir.path().join("out.csv").to_string_lossy().into_owned(),
+            format: SaveFormat::Csv,
+            compression: FileCompressionType::UNCOMPRESSED,
+        }
+    }
+
+    fn dir_entries(dir: &TempDir) -> Vec<String> {
+        fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn csv_null_strings_should_be_read_as_nulls() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("n.csv");
+        fs::write(&path, "id,name,score\n1,a,1.5\n2,NA,NA\n3,c,2\n").unwrap();
+        for engine in [Engine::DataFusion, Engine::Polars] {
+            let mut registry = BackendRegistry::new(ReplOptions {
+                engine,
+                ..Default::default()
+            });
+            let mut opts = ConnectOpts::new(
+                path.to_string_lossy().into_owned(),
+                None,
+                "n".to_string(),
+                None,
+            );
+            opts.read.null = Some("NA".to_string());
+            registry.connect(&opts).await.unwrap();
+
+            let sql = "SELECT id, name, score + 1 AS score FROM n ORDER BY id";
+            let output = registry.sql(sql).await.unwrap();
+            assert_eq!(
+                output.display(OutputFormat::Csv).await.unwrap(),
+                "id,name,score\n1,a,2.5\n2,,\n3,c,3.0",
+                "{:?}",
+                engine
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn save_should_write_the_target_only() {
+        for engine in [Engine::DataFusion, Engine::Polars] {
+            let registry = registry(engine).await;
+            let dir = TempDir::new().unwrap();
+            let file = csv_file(&dir);
+
+            let rows = registry.save("SELECT * FROM j", &file).await.unwrap();
+            assert_eq!(rows, 27);
+            assert_eq!(dir_entries(&dir), ["out.csv"]);
+            assert_eq!(fs::read_to_string(&file.path).unwrap().lines().count(), 28);
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_save_should_keep_the_previous_file() {
+        let queries = [
+            (Engine::DataFusion, "SELECT CAST(name AS INT) FROM j"),
+            (Engine::Polars, "SELECT missing FROM j"),
+        ];
+        for (engine, sql) in queries {
+            let registry = registry(engine).await;
+            let dir = TempDir::new().unwrap();
+            let file = csv_file(&dir);
+            fs::write(&file.path, "old").unwrap();
+
+            assert!(registry.save(sql, &file).await.is_err());
+            assert_eq!(dir_entries(&dir), ["out.csv"]);
+            assert_eq!(fs::read_to_string(&file.path).unwrap(), "old");
+        }
+    }
+
+    async fn sorted_tables(registry: &BackendRegistry) -> Vec<String> {
+        let mut tables = registry.tables().await.unwrap();
+        tables.sort();
+        tables
+    }
+
+    async fn query(registry: &BackendRegistry, sql: &str) -> String {
+        let output = registry.sql(sql).await.unwrap();
+        output.display(OutputFormat::Csv).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn rename_should_move_the_dataset() {
+        for engine in [Engine::DataFusion, Engine::Polars] {
+            let mut registry = registry(engine).await;
+            registry.rename("j", "juventus").await.unwrap();
+
+            assert_eq!(sorted_tables(&registry).await, ["juventus"]);
+            assert_eq!(registry.connected_opts()[0].name, "juventus");
+            assert_eq!(
+                query(&registry, "SELECT * FROM juventus")
+                    .await
+                    .lines()
+                    .count(),
+                28
+            );
+            assert!(registry.sql("SELECT * FROM j").await.is_err());
+
+            assert!(matches!(
+                registry.rename("j", "k").await,
+                Err(BackendError::DatasetNotFound(name)) if name == "j"
+            ));
+            let mut opts = registry.connected_opts().remove(0);
+            opts.name = "j".to_string();
+            registry.connect(&opts).await.unwrap();
+            assert!(matches!(
+                registry.rename("j", "juventus").await,
+                Err(BackendError::DatasetExists(name)) if name == "juventus"
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_rename_should_keep_the_dataset() {
+        let mut registry = registry(Engine::DataFusion).await;
+        let opts = ConnectOpts::new(
+            "fixtures/sample.sqlite".to_string(),
+            None,
+            "db".to_string(),
+            None,
+        );
+        registry.connect(&opts).await.unwrap();
+        registry.sql("CREATE SCHEMA s").await.unwrap();
+        registry
+            .sql("CREATE TABLE t AS SELECT 1 AS x")
+            .await
+            .unwrap();
+        let tables = sorted_tables(&registry).await;
+
+        let err = registry.rename("j", "missing.j").await.unwrap_err();
+        assert!(err.to_string().contains("missing"), "{}", err);
+        for (from, to) in [("j", "t"), ("db", "s")] {
+            assert!(matches!(
+                registry.rename(from, to).await,
+                Err(BackendError::DatasetExists(name)) if name == to
+            ));
+        }
+
+        let names = registry
+            .connected_opts()
+            .into_iter()
+            .map(|opts| opts.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["j", "db"]);
+        assert_eq!(sorted_tables(&registry).await, tables);
+        assert_eq!(
+            query(&registry, "SELECT * FROM j").await.lines().count(),
+            28
+        );
+        assert_eq!(query(&registry, "SELECT * FROM t").await, "x\n1");
+    }
+
+    #[tokio::test]
+    async fn refresh_should_read_the_files_again() {
+        for engine in [Engine::DataFusion, Engine::Polars] {
+            let dir = TempDir::new().unwrap();
+            let path = dir.path().join("r.csv");
+            fs::write(&path, "id\n1\n2\n").unwrap();
+            let mut registry = BackendRegistry::new(ReplOptions {
+                engine,
+                ..Default::default()
+            });
+            let opts = ConnectOpts::new(
+                path.to_string_lossy().into_owned(),
+                None,
+                "r".to_string(),
+                None,
+            );
+            registry.connect(&opts).await.unwrap();
+
+            fs::write(&path, "id,name\n1,a\n2,b\n3,c\n").unwrap();
+            registry.refresh("r").await.unwrap();
+            assert_eq!(sorted_tables(&registry).await, ["r"]);
+            assert_eq!(
+                query(&registry, "SELECT count(*) AS n, max(name) AS name FROM r").await,
+                "n,name\n3,c"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_refresh_should_keep_the_previous_tables() {
+        for engine in [Engine::DataFusion, Engine::Polars] {
+            let dir = TempDir::new().unwrap();
+            let path = dir.path().join("book.xlsx");
+            fs::copy("fixtures/sales.xlsx", &path).unwrap();
+            let mut registry = BackendRegistry::new(ReplOptions {
+                engine,
+                ..Default::default()
+            });
+            let mut opts = ConnectOpts::new(
+                path.to_string_lossy().into_owned(),
+                None,
+                "notes".to_string(),
+                None,
+            );
+            opts.read.sheet = Some("Notes".to_string());
+            registry.connect(&opts).await.unwrap();
+
+            // the sheets are read when connecting, so the rows are still there
+            fs::write(&path, "not a workbook").unwrap();
+            assert!(registry.refresh("notes").await.is_err());
+            assert_eq!(sorted_tables(&registry).await, ["notes"], "{:?}", engine);
+            assert_eq!(
+                query(&registry, "SELECT * FROM notes ORDER BY id").await,
+                "id,note\n1,hello\n2,world"
+            );
+        }
+    }
+
+    async fn registry_schema(registry: &BackendRegistry, name: &str) -> String {
+        let output = registry.schema(name).await.unwrap();
+        output.display(OutputFormat::Csv).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn arrow_files_and_streams_should_round_trip() {
+        let files = [
+            ("out.arrow", SaveFormat::Arrow),
+            ("out.feather", SaveFormat::Arrow),
+            ("out.arrows", SaveFormat::ArrowStream),
+        ];
+        for engine in [Engine::DataFusion, Engine::Polars] {
+            let dir = TempDir::new().unwrap();
+            let mut registry = registry(engine).await;
+            for (i, (name, format)) in files.iter().enumerate() {
+                let file = SaveFile {
+                    path: dir.path().join(name).to_string_lossy().into_owned(),
+                    format: *format,
+                    compression: FileCompressionType::UNCOMPRESSED,
+                };
+                assert_eq!(registry.save("SELECT * FROM j", &file).await.unwrap(), 27);
+
+                let table = format!("a{}", i);
+                let opts = ConnectOpts::new(file.path.clone(), None, table.clone(), None);
+                registry.connect(&opts).await.unwrap();
+                assert_eq!(
+                    registry_schema(&registry, &table).await,
+                    registry_schema(&registry, "j").await,
+                    "{} with {:?}",
+                    name,
+                    engine
+                );
+                let sql = format!("SELECT * FROM {}", table);
+                assert_eq!(query(&registry, &sql).await.lines().count(), 28);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn workbooks_should_register_the_same_table_in_both_engines() {
+        for engine in [Engine::DataFusion, Engine::Polars] {
+            let mut registry = BackendRegistry::new(ReplOptions {
+                engine,
+                ..Default::default()
+            });
+            let mut opts = ConnectOpts::new(
+                "fixtures/sales.xlsx".to_string(),
+                None,
+                "notes".to_string(),
+                None,
+            );
+            let err = registry.connect(&opts).await.unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "Invalid connection string fixtures/sales.xlsx: the workbook has 2 sheets (Q1 Sales, Notes), choose one with --sheet",
+                "{:?}",
+                engine
+            );
+
+            opts.read.sheet = Some("2".to_string());
+            registry.connect(&opts).await.unwrap();
+            assert_eq!(sorted_tables(&registry).await, ["notes"]);
+            assert_eq!(
+                query(&registry, "SELECT note FROM notes WHERE id = 2").await,
+                "note\nworld"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn partitioned_directories_and_globs_should_be_queryable() {
+        for engine in [Engine::DataFusion, Engine::Polars] {
+            let dir = TempDir::new().unwrap();
+            let mut registry = registry(engine).await;
+            for (date, filter) in [("2024-01-01", "kit < 10"), ("2024-01-02", "kit >= 10")] {
+                let part = dir.path().join(format!("date={}", date));
+                fs::create_dir(&part).unwrap();
+                let file = SaveFile {
+                    path: part.join("part-0.parquet").to_string_lossy().into_owned(),
+                    format: SaveFormat::Parquet,
+                    compression: FileCompressionType::UNCOMPRESSED,
+                };
+                let sql = format!("SELECT name, kit FROM j WHERE {}", filter);
+                registry.save(&sql, &file).await.unwrap();
+            }
+
+            let root = dir.path().to_string_lossy().into_owned();
+            let conns = [
+                root.clone(),
+                format!("{}/*/*.parquet", root),
+                format!("{}/date=*/part-*.parquet", root),
+            ];
+            for (i, conn) in conns.into_iter().enumerate() {
+                let table = format!("p{}", i);
+                let opts = ConnectOpts::new(conn.clone(), None, table.clone(), None);
+                registry.connect(&opts).await.unwrap();
+                assert_eq!(
+                    registry_schema(&registry, &table).await,
+                    registry_schema(&registry, "p0").await,
+                    "{} with {:?}",
+                    conn,
+                    engine
+                );
+                let sql = format!(
+                    "SELECT date, min(kit) AS low, max(kit) AS high FROM {} GROUP BY date ORDER BY date",
+                    table
+                );
+                assert_eq!(
+                    query(&registry, &sql).await,
+                    "date,low,high\n2024-01-01,1,8\n2024-01-02,10,77",
+                    "{} with {:?}",
+                    conn,
+                    engine
+                );
+            }
+
+            // only polars reads globs that select partitions
+            let conn = format!("{}/date=*-02/*.parquet", root);
+            let opts = ConnectOpts::new(conn, None, "p".to_string(), None);
+            match engine {
+                Engine::DataFusion => assert!(matches!(
+                    registry.connect(&opts).await,
+                    Err(BackendError::Unsupported(_))
+                )),
+                Engine::Polars => {
+                    registry.connect(&opts).await.unwrap();
+                    assert_eq!(
+                        query(&registry, "SELECT DISTINCT date FROM p").await,
+                        "date\n2024-01-02"
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use clap::Parser;
+
+use crate::{BackendRegistry, CmdExector, Engine};
+
+#[derive(Debug, Parser)]
+pub struct BackendOpts {
+    #[arg(
+        value_enum,
+        help = "The query engine to switch to, show the current one if omitted"
+    )]
+    pub engine: Option<Engine>,
+}
+
+impl BackendOpts {
+    pub fn new(engine: Option<Engine>) -> Self {
+        Self { engine }
+    }
+}
+
+impl CmdExector for BackendOpts {
+    async fn execute(self, backend: &mut BackendRegistry) -> anyhow::Result<String> {
+        let current = backend.engine();
+        let engine = match self.engine {
+            Some(engine) if engine != current => engine,
+            _ => return Ok(format!("Using backend: {}", current)),
+        };
+
+        let failed = backend.switch(engine).await;
+        let mut ret = format!("Switched to backend: {}", engine);
+        for (name, e) in failed {
+            ret.push_str(&format!("\nFailed to register dataset {}: {}", name, e));
+        }
+        Ok(ret)
+    }
+}
@@ -0,0 +1,869 @@
+use std::{
+    fmt,
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use arrow::datatypes::{DataType, TimeUnit};
+use chrono::NaiveDate;
+use clap::{Args, ValueEnum};
+use datafusion::datasource::file_format::file_compression_type::FileCompressionType;
+
+use crate::BackendError;
+
+use super::quote_arg;
+
+/// The format of the files of a dataset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum FileFormat {
+    Csv,
+    #[value(name = "json", alias = "ndjson")]
+    NdJson,
+    Parquet,
+    #[value(alias = "ipc", alias = "feather")]
+    Arrow,
+    Avro,
+    #[value(alias = "xlsx", alias = "xls", alias = "ods")]
+    Excel,
+}
+
+/// The two layouts of Arrow IPC data, files start with the `ARROW1` magic while streams do not.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpcFormat {
+    File,
+    Stream,
+}
+
+/// Options of the file readers, most of them only apply to csv or excel files.
+#[derive(Debug, Clone, Default, Args)]
+pub struct ReadOpts {
+    #[arg(long, value_parser = parse_char, help = "The field delimiter of csv files, `,` if omitted, `\\t` for tabs")]
+    pub delimiter: Option<u8>,
+
+    #[arg(long, value_parser = parse_char, help = "The quote character of csv files, `\"` if omitted")]
+    pub quote: Option<u8>,
+
+    #[arg(long, value_parser = parse_char, help = "The escape character of csv files")]
+    pub escape: Option<u8>,
+
+    #[arg(
+        long,
+        help = "The csv files or sheets have no header row, columns are named column_1, column_2, ..."
+    )]
+    pub no_header: bool,
+
+    #[arg(long, help = "The string read as null in csv files")]
+    pub null: Option<String>,
+
+    #[arg(long, value_parser = parse_char, help = "Skip the lines of csv files starting with this character")]
+    pub comment: Option<u8>,
+
+    #[arg(long, help = "The number of rows the schema is inferred from")]
+    pub infer_rows: Option<usize>,
+
+    #[arg(
+        long,
+        help = "The columns of csv, json or excel files instead of inferring them, e.g. \"id bigint, name varchar\""
+    )]
+    pub schema: Option<ColumnTypes>,
+
+    #[arg(
+        long,
+        help = "The sheet of an excel file to register, by name or position starting at 1, required if the workbook has several"
+    )]
+    pub sheet: Option<String>,
+
+    #[arg(
+        long,
+        conflicts_with = "no_header",
+        help = "The row number of the header in every sheet read, the first row of the range if omitted"
+    )]
+    pub header_row: Option<u32>,
+
+    #[arg(long, help = "The cells to read from sheets, e.g. B2:F100")]
+    pub range: Option<CellRange>,
+}
+
+/// Columns given as `name type, ...`, kept as written to record them in sessions.
+#[derive(Debug, Clone)]
+pub struct ColumnTypes {
+    spec: String,
+    pub columns: Vec<(String, DataType)>,
+}
+
+/// A rectangle of cells written as `B2:F100`, kept as written to record it in sessions.
+#[derive(Debug, Clone)]
+pub struct CellRange {
+    spec: String,
+    /// The first and last cells as zero based `(row, column)`.
+    pub start: (u32, u32),
+    pub end: (u32, u32),
+}
+
+#[derive(Debug, Clone)]
+pub enum DatasetConn {
+    Postgres(String),
+    MySql(String),
+    Sqlite(String),
+    Csv(FileOpts),
+    Parquet(FileOpts),
+    NdJson(FileOpts),
+    Arrow(FileOpts, IpcFormat),
+    Avro(FileOpts),
+    Excel(FileOpts),
+}
+
+#[derive(Debug, Clone)]
+pub struct FileOpts {
+    /// A file, a directory or a glob pattern.
+    pub filename: String,
+    /// The extension of the files to read.
+    pub ext: String,
+    pub compression: FileCompressionType,
+    /// Hive partition columns taken from directory names like `date=2024-08-29`.
+    pub partitions: Vec<(String, DataType)>,
+}
+
+impl DatasetConn {
+    /// Work out the dataset behind a connection string. Directories and globs
+    /// are looked into for the format of their files and their Hive partitions.
+    pub fn resolve(conn_str: &str, format: Option<FileFormat>) -> Result<Self, BackendError> {
+        let invalid = |reason: &str| BackendError::InvalidConnection {
+            conn: conn_str.to_string(),
+            reason: reason.to_string(),
+        };
+
+        let conn = conn_str.to_string();
+        if conn.starts_with("postgres://") || conn.starts_with("mysql://") {
+            if format.is_some() {
+                return Err(invalid("--format only applies to files"));
+            }
+            return Ok(if conn.starts_with("postgres://") {
+                DatasetConn::Postgres(conn)
+            } else {
+                DatasetConn::MySql(conn)
+            });
+        }
+        if let Some(path) = conn.strip_prefix("sqlite://") {
+            return Ok(DatasetConn::Sqlite(path.to_string()));
+        }
+        if format.is_none()
+            && [".sqlite", ".sqlite3", ".db"]
+                .iter()
+                .any(|ext| conn.ends_with(ext))
+        {
+            return Ok(DatasetConn::Sqlite(conn));
+        }
+
+        let (files, base) = list_files(conn_str)?;
+        // the format of a directory or glob is the one of its files
+        let sample = files
+            .first()
+            .map(PathBuf::as_path)
+            .unwrap_or(Path::new(conn_str));
+        let filename = sample
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| invalid("not a file"))?;
+        let magic = read_magic(sample);
+        let detected = detect_format(filename, &magic);
+        let format = match format.or(detected.format) {
+            Some(format) => format,
+            None if detected.ext.is_empty() => {
+                return Err(invalid("no file extension, use --format to set it"))
+            }
+            None => {
+                return Err(invalid(&format!(
+                    "Invalid file extension: {}, use --format to set it",
+                    detected.ext
+                )))
+            }
+        };
+        if detected.compression.is_compressed()
+            && !matches!(format, FileFormat::Csv | FileFormat::NdJson)
+        {
+            return Err(invalid(&format!("{} files cannot be compressed", format)));
+        }
+
+        let opts = FileOpts {
+            filename: conn,
+            ext: detected.ext,
+            compression: detected.compression,
+            partitions: base
+                .map(|base| hive_partitions(&base, &files))
+                .unwrap_or_default(),
+        };
+        Ok(match format {
+            FileFormat::Csv => DatasetConn::Csv(opts),
+            FileFormat::NdJson => DatasetConn::NdJson(opts),
+            FileFormat::Parquet => DatasetConn::Parquet(opts),
+            FileFormat::Arrow if magic.starts_with(ARROW_MAGIC) => {
+                DatasetConn::Arrow(opts, IpcFormat::File)
+            }
+            FileFormat::Arrow => DatasetConn::Arrow(opts, IpcFormat::Stream),
+            FileFormat::Avro => DatasetConn::Avro(opts),
+            FileFormat::Excel => DatasetConn::Excel(opts),
+        })
+    }
+
+    /// The format shown by `list`.
+    pub fn format_name(&self) -> &'static str {
+        match self {
+            DatasetConn::Postgres(_) => "postgres",
+            DatasetConn::MySql(_) => "mysql",
+            DatasetConn::Sqlite(_) => "sqlite",
+            DatasetConn::Csv(_) => "csv",
+            DatasetConn::Parquet(_) => "parquet",
+            DatasetConn::NdJson(_) => "json",
+            DatasetConn::Arrow(_, IpcFormat::File) => "arrow",
+            DatasetConn::Arrow(_, IpcFormat::Stream) => "arrow stream",
+            DatasetConn::Avro(_) => "avro",
+            DatasetConn::Excel(_) => "excel",
+        }
+    }
+}
+
+impl FileOpts {
+    /// The file of a dataset some engines can only read one file of.
+    pub fn single_file(&self, what: &str) -> Result<&str, BackendError> {
+        if split_glob(&self.filename).is_some() || Path::new(&self.filename).is_dir() {
+            return Err(BackendError::Unsupported(format!(
+                "directories and globs of {}",
+                what
+            )));
+        }
+        Ok(&self.filename)
+    }
+
+    /// The data files of the dataset.
+    pub fn files(&self) -> Result<Vec<PathBuf>, BackendError> {
+        match list_files(&self.filename)? {
+            (files, Some(_)) => Ok(files),
+            (_, None) => Ok(vec![PathBuf::from(&self.filename)]),
+        }
+    }
+}
+
+impl ReadOpts {
+    /// The flags given on the command line, as `--flag value`.
+    pub fn to_args(&self) -> Vec<String> {
+        let char_arg = |c: u8| match c {
+            b'\t' => "\\t".to_string(),
+            c => quote_arg(&(c as char).to_string()),
+        };
+        let mut args = vec![];
+        let chars = [
+            ("--delimiter", self.delimiter),
+            ("--quote", self.quote),
+            ("--escape", self.escape),
+        ];
+        for (flag, c) in chars {
+            if let Some(c) = c {
+                args.push(format!("{} {}", flag, char_arg(c)));
+            }
+        }
+        if self.no_header {
+            args.push("--no-header".to_string());
+        }
+        if let Some(null) = &self.null {
+            args.push(format!("--null {}", quote_arg(null)));
+        }
+        if let Some(c) = self.comment {
+            args.push(format!("--comment {}", char_arg(c)));
+        }
+        if let Some(rows) = self.infer_rows {
+            args.push(format!("--infer-rows {}", rows));
+        }
+        if let Some(schema) = &self.schema {
+            args.push(format!("--schema {}", quote_arg(&schema.to_string())));
+        }
+        if let Some(sheet) = &self.sheet {
+            args.push(format!("--sheet {}", quote_arg(sheet)));
+        }
+        if let Some(row) = self.header_row {
+            args.push(format!("--header-row {}", row));
+        }
+        if let Some(range) = &self.range {
+            args.push(format!("--range {}", range));
+        }
+        args
+    }
+
+    /// The first flag given that does not apply to the dataset.
+    pub fn unused_flag(&self, dataset: &DatasetConn) -> Option<&'static str> {
+        let csv = matches!(dataset, DatasetConn::Csv(_));
+        let excel = matches!(dataset, DatasetConn::Excel(_));
+        let inferred = csv || excel || matches!(dataset, DatasetConn::NdJson(_));
+        [
+            ("--delimiter", self.delimiter.is_some(), csv),
+            ("--quote", self.quote.is_some(), csv),
+            ("--escape", self.escape.is_some(), csv),
+            ("--no-header", self.no_header, csv || excel),
+            ("--null", self.null.is_some(), csv),
+            ("--comment", self.comment.is_some(), csv),
+            ("--infer-rows", self.infer_rows.is_some(), inferred),
+            ("--schema", self.schema.is_some(), inferred),
+            ("--sheet", self.sheet.is_some(), excel),
+            ("--header-row", self.header_row.is_some(), excel),
+            ("--range", self.range.is_some(), excel),
+        ]
+        .into_iter()
+        .find_map(|(flag, given, applies)| (given && !applies).then_some(flag))
+    }
+}
+
+/// A single ascii character, or `\t` for tabs.
+fn parse_char(s: &str) -> Result<u8, String> {
+    match s.as_bytes() {
+        [c] if c.is_ascii() => Ok(*c),
+        b"\\t" | b"tab" => Ok(b'\t'),
+        _ => Err(format!("expect a single ascii character, got `{}`", s)),
+    }
+}
+
+impl FromStr for ColumnTypes {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let columns = s
+            .split(',')
+            .map(|column| {
+                let mut parts = column.split_whitespace();
+                match (parts.next(), parts.next(), parts.next()) {
+                    (Some(name), Some(data_type), None) => {
+                        Ok((name.to_string(), column_type(data_type)?))
+                    }
+                    _ => Err(format!("expect `name type`, got `{}`", column.trim())),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            spec: s.trim().to_string(),
+            columns,
+        })
+    }
+}
+
+impl fmt::Display for ColumnTypes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.spec)
+    }
+}
+
+impl FromStr for CellRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("expect cells like B2:F100, got `{}`", s);
+        let (start, end) = s.split_once(':').ok_or_else(invalid)?;
+        let (start, end) = (parse_cell(start), parse_cell(end));
+        match (start, end) {
+            (Some(start), Some(end)) if start.0 <= end.0 && start.1 <= end.1 => Ok(Self {
+                spec: s.to_uppercase(),
+                start,
+                end,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for CellRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.spec)
+    }
+}
+
+/// The zero based `(row, column)` of a cell like `B2`.
+fn parse_cell(cell: &str) -> Option<(u32, u32)> {
+    let digits = cell.find(|c: char| c.is_ascii_digit())?;
+    let (letters, row) = cell.split_at(digits);
+    if letters.is_empty() || !letters.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let column = letters
+        .to_ascii_uppercase()
+        .bytes()
+        .try_fold(0u32, |n, c| {
+            n.checked_mul(26)?.checked_add((c - b'A' + 1) as u32)
+        })?;
+    let row = row.parse::<u32>().ok().filter(|row| *row > 0)?;
+    Some((row - 1, column - 1))
+}
+
+/// The arrow type of a SQL type name.
+fn column_type(name: &str) -> Result<DataType, String> {
+    let data_type = match name.to_lowercase().as_str() {
+        "boolean" | "bool" => DataType::Boolean,
+        "tinyint" | "int8" => DataType::Int8,
+        "smallint" | "int16" => DataType::Int16,
+        "int" | "integer" | "int32" => DataType::Int32,
+        "bigint" | "int64" => DataType::Int64,
+        "float" | "real" | "float32" => DataType::Float32,
+        "double" | "float64" => DataType::Float64,
+        "varchar" | "string" | "text" | "utf8" => DataType::Utf8,
+        "date" => DataType::Date32,
+        "timestamp" => DataType::Timestamp(TimeUnit::Nanosecond, None),
+        _ => return Err(format!("unknown column type `{}`", name)),
+    };
+    Ok(data_type)
+}
+
+/// The data files of a directory or glob pattern along with the directory they are listed from,
+/// nothing for a single file.
+fn list_files(path: &str) -> Result<(Vec<PathBuf>, Option<PathBuf>), BackendError> {
+    if split_glob(path).is_none() && !Path::new(path).is_dir() {
+        if !Path::new(path).exists() {
+            return Err(BackendError::FileNotFound(path.to_string()));
+        }
+        return Ok((vec![], None));
+    }
+
+    let (pattern, base) = if let Some((base, _)) = split_glob(path) {
+        (path.to_string(), base)
+    } else {
+        (
+            format!("{}/**/*", path.trim_end_matches('/')),
+            PathBuf::from(path),
+        )
+    };
+    let files = glob::glob(&pattern)
+        .map_err(|e| BackendError::InvalidConnection {
+            conn: path.to_string(),
+            reason: e.to_string(),
+        })?
+        .filter_map(Result::ok)
+        .filter(|p| p.is_file() && !is_metadata_file(p))
+        .collect::<Vec<_>>();
+    if files.is_empty() {
+        return Err(BackendError::FileNotFound(path.to_string()));
+    }
+    Ok((files, Some(base)))
+}
+
+/// Split a glob pattern into the directory before the first segment with a wildcard,
+/// and the segments from there on.
+pub fn split_glob(path: &str) -> Option<(PathBuf, Vec<String>)> {
+    if !path.contains(['*', '?', '[']) {
+        return None;
+    }
+    let mut base = PathBuf::new();
+    let mut segments = vec![];
+    for c in Path::new(path).components() {
+        let c = c.as_os_str().to_string_lossy();
+        if segments.is_empty() && !c.contains(['*', '?', '[']) {
+            base.push(c.as_ref());
+        } else {
+            segments.push(c.to_string());
+        }
+    }
+    Some((base, segments))
+}
+
+const ARROW_MAGIC: &[u8] = b"ARROW1";
+
+/// The first bytes of a file, nothing if it cannot be read.
+fn read_magic(path: &Path) -> Vec<u8> {
+    let mut magic = vec![];
+    if let Ok(file) = File::open(path) {
+        let _ = file.take(8).read_to_end(&mut magic);
+    }
+    magic
+}
+
+/// Files written next to the data, like `_SUCCESS` or `.part-0.parquet.crc`.
+fn is_metadata_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_none_or(|name| name.starts_with(['.', '_']))
+}
+
+/// What the name and first bytes of a file tell of it.
+#[derive(Debug, PartialEq, Eq)]
+struct Detected {
+    format: Option<FileFormat>,
+    /// The extension files are listed by, with the compression suffix, e.g. `csv.gz`.
+    ext: String,
+    compression: FileCompressionType,
+}
+
+/// Detect the format and compression of a file. The first bytes take precedence over the name,
+/// which is all there is to tell csv and json apart.
+fn detect_format(filename: &str, magic: &[u8]) -> Detected {
+    let exts = filename.split('.').skip(1).collect::<Vec<_>>();
+    let (format_ext, suffix) = match exts.as_slice() {
+        [.., format_ext, suffix] if compression_of_ext(suffix).is_some() => {
+            (Some(*format_ext), Some(*suffix))
+        }
+        [suffix] if compression_of_ext(suffix).is_some() => (None, Some(*suffix)),
+        [.., format_ext] => (Some(*format_ext), None),
+        [] => (None, None),
+    };
+    let ext = [format_ext, suffix]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(".");
+
+    let compression = compression_of_magic(magic)
+        .or_else(|| suffix.and_then(compression_of_ext))
+        .unwrap_or(FileCompressionType::UNCOMPRESSED);
+    let format = if compression.is_compressed() {
+        None
+    } else {
+        format_of_magic(magic)
+    };
+    Detected {
+        format: format.or_else(|| format_ext.and_then(format_of_ext)),
+        ext,
+        compression,
+    }
+}
+
+fn format_of_ext(ext: &str) -> Option<FileFormat> {
+    Some(match ext.to_lowercase().as_str() {
+        "csv" => FileFormat::Csv,
+        "json" | "jsonl" | "ndjson" => FileFormat::NdJson,
+        "parquet" => FileFormat::Parquet,
+        "arrow" | "arrows" | "feather" | "ipc" => FileFormat::Arrow,
+        "avro" => FileFormat::Avro,
+        "xlsx" | "xlsm" | "xlsb" | "xls" | "ods" => FileFormat::Excel,
+        _ => return None,
+    })
+}
+
+fn compression_of_ext(ext: &str) -> Option<FileCompressionType> {
+    Some(match ext.to_lowercase().as_str() {
+        "gz" | "gzip" => FileCompressionType::GZIP,
+        "bz2" => FileCompressionType::BZIP2,
+        "xz" => FileCompressionType::XZ,
+        "zst" | "zstd" => FileCompressionType::ZSTD,
+        _ => return None,
+    })
+}
+
+fn format_of_magic(magic: &[u8]) -> Option<FileFormat> {
+    if magic.starts_with(b"PAR1") {
+        Some(FileFormat::Parquet)
+    } else if magic.starts_with(ARROW_MAGIC) {
+        Some(FileFormat::Arrow)
+    } else if magic.starts_with(b"Obj\x01") {
+        Some(FileFormat::Avro)
+    } else {
+        None
+    }
+}
+
+fn compression_of_magic(magic: &[u8]) -> Option<FileCompressionType> {
+    if magic.starts_with(&[0x1f, 0x8b]) {
+        Some(FileCompressionType::GZIP)
+    } else if magic.starts_with(b"BZh") {
+        Some(FileCompressionType::BZIP2)
+    } else if magic.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+        Some(FileCompressionType::XZ)
+    } else if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        Some(FileCompressionType::ZSTD)
+    } else {
+        None
+    }
+}
+
+/// The partition columns of files laid out as `base/key=value/.../file`, typed after their values.
+/// Nothing is partitioned unless every file follows the same layout.
+fn hive_partitions(base: &Path, files: &[PathBuf]) -> Vec<(String, DataType)> {
+    let mut columns: Vec<(String, Vec<String>)> = vec![];
+    for (i, file) in files.iter().enumerate() {
+        let Some(dirs) = file.strip_prefix(base).ok().and_then(Path::parent) else {
+            return vec![];
+        };
+        let Some(parts) = dirs
+            .iter()
+            .map(|dir| dir.to_str()?.split_once('='))
+            .collect::<Option<Vec<_>>>()
+        else {
+            return vec![];
+        };
+
+        if i == 0 {
+            columns = parts
+                .iter()
+                .map(|(key, value)| (key.to_string(), vec![value.to_string()]))
+                .collect();
+        } else if parts.len() != columns.len()
+            || parts
+                .iter()
+                .zip(&columns)
+                .any(|((key, _), (name, _))| key != name)
+        {
+            return vec![];
+        } else {
+            for ((_, value), (_, values)) in parts.iter().zip(&mut columns) {
+                values.push(value.to_string());
+            }
+        }
+    }
+
+    columns
+        .into_iter()
+        .map(|(name, values)| (name, partition_type(&values)))
+        .collect()
+}
+
+fn partition_type(values: &[String]) -> DataType {
+    // the directory Hive writes null values to
+    let values = values
+        .iter()
+        .filter(|v| *v != "__HIVE_DEFAULT_PARTITION__")
+        .collect::<Vec<_>>();
+    if values.is_empty() {
+        DataType::Utf8
+    } else if values.iter().all(|v| v.parse::<i64>().is_ok()) {
+        DataType::Int64
+    } else if values
+        .iter()
+        .all(|v| NaiveDate::parse_from_str(v, "%Y-%m-%d").is_ok())
+    {
+        DataType::Date32
+    } else {
+        DataType::Utf8
+    }
+}
+
+impl std::fmt::Display for FileFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = self.to_possible_value().expect("no skipped variants");
+        write!(f, "{}", value.get_name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, sync::Arc};
+
+    use super::*;
+
+    const GZIP: &[u8] = &[0x1f, 0x8b, 0x08, 0x00];
+    const BZIP2: &[u8] = b"BZh91AY";
+    const XZ: &[u8] = &[0xfd, b'7', b'z', b'X', b'Z', 0x00];
+    const ZSTD: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+    const CSV: &[u8] = b"a,b\n1,2";
+
+    /// A file name, its first bytes, and the expected format, extension and compression.
+    type Case = (
+        &'static str,
+        &'static [u8],
+        Option<FileFormat>,
+        &'static str,
+        FileCompressionType,
+    );
+
+    #[test]
+    fn detect_format_should_work() {
+        use FileCompressionType as C;
+        use FileFormat::*;
+
+        let cases: &[Case] = &[
+            // plain files
+            ("data.csv", CSV, Some(Csv), "csv", C::UNCOMPRESSED),
+            ("data.CSV", CSV, Some(Csv), "CSV", C::UNCOMPRESSED),
+            ("data.json", b"{}", Some(NdJson), "json", C::UNCOMPRESSED),
+            ("data.jsonl", b"{}", Some(NdJson), "jsonl", C::UNCOMPRESSED),
+            (
+                "data.ndjson",
+                b"{}",
+                Some(NdJson),
+                "ndjson",
+                C::UNCOMPRESSED,
+            ),
+            (
+                "data.parquet",
+                b"PAR1",
+                Some(Parquet),
+                "parquet",
+                C::UNCOMPRESSED,
+            ),
+            (
+                "data.arrow",
+                b"ARROW1",
+                Some(Arrow),
+                "arrow",
+                C::UNCOMPRESSED,
+            ),
+            (
+                "data.feather",
+                b"ARROW1",
+                Some(Arrow),
+                "feather",
+                C::UNCOMPRESSED,
+            ),
+            (
+                "data.arrows",
+                &[0xff; 4],
+                Some(Arrow),
+                "arrows",
+                C::UNCOMPRESSED,
+            ),
+            // the IPC file magic and a stream, which starts with a continuation marker
+            (
+                "data.arrows",
+                b"ARROW1",
+                Some(Arrow),
+                "arrows",
+                C::UNCOMPRESSED,
+            ),
+            (
+                "data.arrow",
+                &[0xff; 4],
+                Some(Arrow),
+                "arrow",
+                C::UNCOMPRESSED,
+            ),
+            ("data.avro", b"Obj\x01", Some(Avro), "avro", C::UNCOMPRESSED),
+            (
+                "book.xlsx",
+                b"PK\x03\x04",
+                Some(Excel),
+                "xlsx",
+                C::UNCOMPRESSED,
+            ),
+            (
+                "book.ods",
+                b"PK\x03\x04",
+                Some(Excel),
+                "ods",
+                C::UNCOMPRESSED,
+            ),
+            // compressed files
+            ("data.csv.gz", GZIP, Some(Csv), "csv.gz", C::GZIP),
+            ("data.csv.bz2", BZIP2, Some(Csv), "csv.bz2", C::BZIP2),
+            ("data.csv.xz", XZ, Some(Csv), "csv.xz", C::XZ),
+            ("data.csv.zst", ZSTD, Some(Csv), "csv.zst", C::ZSTD),
+            ("data.csv.zstd", ZSTD, Some(Csv), "csv.zstd", C::ZSTD),
+            ("data.jsonl.gz", GZIP, Some(NdJson), "jsonl.gz", C::GZIP),
+            ("data.ndjson.xz", XZ, Some(NdJson), "ndjson.xz", C::XZ),
+            // dotted names
+            (
+                "events.2024-08-29.csv.gz",
+                GZIP,
+                Some(Csv),
+                "csv.gz",
+                C::GZIP,
+            ),
+            ("v1.2.json", b"{}", Some(NdJson), "json", C::UNCOMPRESSED),
+            // the content wins over the name
+            ("data.csv", GZIP, Some(Csv), "csv", C::GZIP),
+            ("data.csv.gz", CSV, Some(Csv), "csv.gz", C::GZIP),
+            ("export", b"PAR1", Some(Parquet), "", C::UNCOMPRESSED),
+            ("export.bin", b"ARROW1", Some(Arrow), "bin", C::UNCOMPRESSED),
+            ("export.dat", b"Obj\x01", Some(Avro), "dat", C::UNCOMPRESSED),
+            ("data.json", b"PAR1", Some(Parquet), "json", C::UNCOMPRESSED),
+            // unknown formats are left to --format
+            ("dump.txt", CSV, None, "txt", C::UNCOMPRESSED),
+            ("dump.txt.gz", GZIP, None, "txt.gz", C::GZIP),
+            ("dump.gz", GZIP, None, "gz", C::GZIP),
+            ("dump", GZIP, None, "", C::GZIP),
+            ("dump", b"", None, "", C::UNCOMPRESSED),
+            ("archive.tar.zst", ZSTD, None, "tar.zst", C::ZSTD),
+        ];
+
+        for (filename, magic, format, ext, compression) in cases {
+            let expected = Detected {
+                format: *format,
+                ext: ext.to_string(),
+                compression: *compression,
+            };
+            assert_eq!(detect_format(filename, magic), expected, "{}", filename);
+        }
+    }
+
+    #[test]
+    fn resolve_should_read_magic_bytes() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let dotted = dir.path().join("my.v2.data");
+        fs::create_dir(&dotted).unwrap();
+        let file = dotted.join("part-0");
+        fs::write(&file, GZIP).unwrap();
+
+        let conn = file.to_str().unwrap();
+        assert!(matches!(
+            DatasetConn::resolve(conn, None),
+            Err(BackendError::InvalidConnection { .. })
+        ));
+        match DatasetConn::resolve(conn, Some(FileFormat::Csv)).unwrap() {
+            DatasetConn::Csv(opts) => assert_eq!(opts.compression, FileCompressionType::GZIP),
+            other => panic!("expect a csv dataset, got {:?}", other),
+        }
+        assert!(matches!(
+            DatasetConn::resolve(conn, Some(FileFormat::Parquet)),
+            Err(BackendError::InvalidConnection { .. })
+        ));
+    }
+
+    #[test]
+    fn resolve_should_tell_arrow_files_from_streams() {
+        use arrow::{
+            array::{Int64Array, RecordBatch},
+            ipc::writer::{FileWriter, StreamWriter},
+        };
+
+        let batch =
+            RecordBatch::try_from_iter(vec![("id", Arc::new(Int64Array::from(vec![1, 2])) as _)])
+                .unwrap();
+        let dir = tempfile::TempDir::new().unwrap();
+        // the names are swapped, the content decides
+        let file = dir.path().join("data.arrows");
+        let mut writer =
+            FileWriter::try_new(fs::File::create(&file).unwrap(), &batch.schema()).unwrap();
+        writer.write(&batch).unwrap();
+        writer.finish().unwrap();
+        let stream = dir.path().join("data.arrow");
+        let mut writer =
+            StreamWriter::try_new(fs::File::create(&stream).unwrap(), &batch.schema()).unwrap();
+        writer.write(&batch).unwrap();
+        writer.finish().unwrap();
+
+        assert!(matches!(
+            DatasetConn::resolve(file.to_str().unwrap(), None).unwrap(),
+            DatasetConn::Arrow(_, IpcFormat::File)
+        ));
+        assert!(matches!(
+            DatasetConn::resolve(stream.to_str().unwrap(), None).unwrap(),
+            DatasetConn::Arrow(_, IpcFormat::Stream)
+        ));
+    }
+
+    #[test]
+    fn hive_partitions_should_follow_the_directories() {
+        let base = Path::new("/data");
+        let files = |paths: &[&str]| paths.iter().map(|p| base.join(p)).collect::<Vec<_>>();
+
+        let partitioned = files(&[
+            "date=2024-01-01/n=1/part-0.parquet",
+            "date=2024-01-02/n=__HIVE_DEFAULT_PARTITION__/part-0.parquet",
+            "date=2024-01-02/n=2/part-1.parquet",
+        ]);
+        assert_eq!(
+            hive_partitions(base, &partitioned),
+            [
+                ("date".to_string(), DataType::Date32),
+                ("n".to_string(), DataType::Int64)
+            ]
+        );
+        let named = files(&["city=Turin/a.csv", "city=Milan/b.csv"]);
+        assert_eq!(
+            hive_partitions(base, &named),
+            [("city".to_string(), DataType::Utf8)]
+        );
+
+        for unpartitioned in [
+            files(&["a.parquet", "b.parquet"]),
+            files(&["2024/a.parquet"]),
+            files(&["date=2024-01-01/a.parquet", "b.parquet"]),
+            files(&["date=2024-01-01/a.parquet", "day=2024-01-02/b.parquet"]),
+        ] {
+            assert!(hive_partitions(base, &unpartitioned).is_empty());
+        }
+    }
+}
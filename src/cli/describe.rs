@@ -1,34 +1,102 @@
-use clap::{ArgMatches, Parser};
+use clap::{value_parser, Parser};
 
-use crate::{CmdExector, ReplContext, ReplDisplay, ReplMsg};
+use crate::{backend::DescribeMethod, Backend, BackendRegistry, CmdExector, ReplDisplay};
 
-use super::ReplResult;
+const DEFAULT_PERCENTILES: [&str; 4] = ["25", "75", "95", "99"];
 
 #[derive(Debug, Parser)]
 pub struct DescribeOpts {
     #[arg(help = "The name of the dataset")]
     pub name: String,
-}
 
-pub fn describe(args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
-    let name = args
-        .get_one::<String>("name")
-        .expect("expect name")
-        .to_string();
+    #[arg(
+        long,
+        value_delimiter = ',',
+        num_args = 0..=1,
+        default_missing_values = DEFAULT_PERCENTILES,
+        value_parser = value_parser!(u8).range(1..100),
+        help = "Percentiles of numeric columns, e.g. 10,90, or p25, p75, p95 and p99 without a value, approximate with the datafusion engine"
+    )]
+    pub percentiles: Option<Vec<u8>>,
+
+    #[arg(long, help = "The approximate number of distinct values")]
+    pub approx_distinct: bool,
+
+    #[arg(long, help = "The exact number of distinct values")]
+    pub distinct: bool,
+
+    #[arg(long, help = "The most frequent value and how many times it occurs")]
+    pub top: bool,
+
+    #[arg(long, help = "The min, average and max length of string columns")]
+    pub lengths: bool,
 
-    let (msg, rx) = ReplMsg::new(DescribeOpts::new(name));
-    Ok(ctx.send(msg, rx))
+    #[arg(long, help = "The number of true and false values of boolean columns")]
+    pub bools: bool,
+
+    #[arg(long, help = "Every statistic, with the default percentiles")]
+    pub all: bool,
 }
 
 impl DescribeOpts {
     pub fn new(name: String) -> Self {
-        Self { name }
+        Self {
+            name,
+            percentiles: None,
+            approx_distinct: false,
+            distinct: false,
+            top: false,
+            lengths: false,
+            bools: false,
+            all: false,
+        }
+    }
+
+    /// The statistics to compute, in output order.
+    pub fn methods(&self) -> Vec<DescribeMethod> {
+        let mut methods = DescribeMethod::DEFAULT.to_vec();
+        let mut percentiles = match (&self.percentiles, self.all) {
+            (Some(percentiles), _) => percentiles.clone(),
+            (None, true) => DEFAULT_PERCENTILES
+                .iter()
+                .map(|p| p.parse().expect("valid percentile"))
+                .collect(),
+            (None, false) => vec![],
+        };
+        percentiles.sort_unstable();
+        percentiles.dedup();
+        methods.extend(percentiles.into_iter().map(DescribeMethod::Percentile));
+
+        let optional = [
+            (self.approx_distinct, &[DescribeMethod::ApproxDistinct][..]),
+            (self.distinct, &[DescribeMethod::Distinct]),
+            (self.top, &[DescribeMethod::Top, DescribeMethod::TopCount]),
+            (
+                self.lengths,
+                &[
+                    DescribeMethod::MinLength,
+                    DescribeMethod::AvgLength,
+                    DescribeMethod::MaxLength,
+                ],
+            ),
+            (
+                self.bools,
+                &[DescribeMethod::TrueCount, DescribeMethod::FalseCount],
+            ),
+        ];
+        for (selected, group) in optional {
+            if selected || self.all {
+                methods.extend_from_slice(group);
+            }
+        }
+        methods
     }
 }
 
 impl CmdExector for DescribeOpts {
-    async fn execute<T: crate::Backend>(self, backend: &mut T) -> anyhow::Result<String> {
-        let df = backend.describe(&self.name).await?;
-        df.display().await
+    async fn execute(self, backend: &mut BackendRegistry) -> anyhow::Result<String> {
+        let methods = self.methods();
+        let df = backend.describe(&self.name, &methods).await?;
+        Ok(df.display(backend.format()).await?)
     }
 }
@@ -0,0 +1,16 @@
+use clap::Parser;
+
+use crate::{Backend, BackendRegistry, CmdExector};
+
+#[derive(Debug, Parser)]
+pub struct DisconnectOpts {
+    #[arg(help = "The name of the dataset")]
+    pub name: String,
+}
+
+impl CmdExector for DisconnectOpts {
+    async fn execute(self, backend: &mut BackendRegistry) -> anyhow::Result<String> {
+        backend.disconnect(&self.name).await?;
+        Ok(format!("Disconnected dataset: {}", self.name))
+    }
+}
@@ -0,0 +1,54 @@
+use clap::{Parser, ValueEnum};
+use serde::Deserialize;
+
+use crate::{BackendRegistry, CmdExector};
+
+/// How query results are printed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Csv,
+    Tsv,
+    Json,
+    #[value(name = "ndjson")]
+    NdJson,
+    Markdown,
+    /// One block per record, with a line per column.
+    Vertical,
+}
+
+#[derive(Debug, Parser)]
+pub struct FormatOpts {
+    #[arg(
+        value_enum,
+        help = "The output format of results, show the current one if omitted"
+    )]
+    pub format: Option<OutputFormat>,
+}
+
+impl FormatOpts {
+    pub fn new(format: Option<OutputFormat>) -> Self {
+        Self { format }
+    }
+}
+
+impl CmdExector for FormatOpts {
+    async fn execute(self, backend: &mut BackendRegistry) -> anyhow::Result<String> {
+        match self.format {
+            Some(format) => {
+                backend.set_format(format);
+                Ok(format!("Output format: {}", format))
+            }
+            None => Ok(format!("Output format: {}", backend.format())),
+        }
+    }
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = self.to_possible_value().expect("no skipped variants");
+        write!(f, "{}", value.get_name())
+    }
+}
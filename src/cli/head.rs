@@ -1,29 +1,19 @@
-use clap::{ArgMatches, Parser};
+use clap::Parser;
 
-use crate::{CmdExector, ReplContext, ReplDisplay, ReplMsg};
-
-use super::ReplResult;
+use crate::{Backend, BackendRegistry, CmdExector, ReplDisplay};
 
 #[derive(Debug, Parser)]
 pub struct HeadOpts {
     #[arg(help = "The name of the dataset")]
     pub name: String,
-    #[arg(short, long, help = "The number of rows to display")]
+    #[arg(
+        short,
+        long,
+        help = "The number of rows to display, 5 unless set in the config file"
+    )]
     pub n: Option<usize>,
 }
 
-pub fn head(args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
-    let name = args
-        .get_one::<String>("name")
-        .expect("expect name")
-        .to_string();
-
-    let n = args.get_one::<usize>("n").copied();
-
-    let (msg, rx) = ReplMsg::new(HeadOpts::new(name, n));
-    Ok(ctx.send(msg, rx))
-}
-
 impl HeadOpts {
     pub fn new(name: String, n: Option<usize>) -> Self {
         Self { name, n }
@@ -31,8 +21,10 @@ impl HeadOpts {
 }
 
 impl CmdExector for HeadOpts {
-    async fn execute<T: crate::Backend>(self, backend: &mut T) -> anyhow::Result<String> {
-        let df = backend.head(&self.name, self.n.unwrap_or(5)).await?;
-        df.display().await
+    async fn execute(self, backend: &mut BackendRegistry) -> anyhow::Result<String> {
+        let df = backend
+            .head(&self.name, self.n.unwrap_or(backend.head_size()))
+            .await?;
+        Ok(df.display(backend.format()).await?)
     }
 }
@@ -1,20 +1,13 @@
-use clap::{ArgMatches, Parser};
+use clap::Parser;
 
-use crate::{Backend, CmdExector, ReplContext, ReplDisplay, ReplMsg};
-
-use super::ReplResult;
+use crate::{BackendRegistry, CmdExector, ReplDisplay};
 
 #[derive(Debug, Parser)]
 pub struct ListOpts;
 
-pub fn list(_args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
-    let (msg, rx) = ReplMsg::new(ListOpts);
-    Ok(ctx.send(msg, rx))
-}
-
 impl CmdExector for ListOpts {
-    async fn execute<T: Backend>(self, backend: &mut T) -> anyhow::Result<String> {
+    async fn execute(self, backend: &mut BackendRegistry) -> anyhow::Result<String> {
         let df = backend.list().await?;
-        df.display().await
+        Ok(df.display(backend.format()).await?)
     }
 }
@@ -1,26 +1,143 @@
+mod backend;
 mod connect;
+mod dataset;
 mod describe;
+mod disconnect;
+mod format;
 mod head;
 mod list;
+mod parquet_meta;
+mod refresh;
+mod rename;
+mod save;
 mod schema;
+mod session;
+mod set;
+mod show;
 mod sql;
 
-pub use self::{
-    connect::connect, describe::describe, head::head, list::list, schema::schema, sql::sql,
-};
 pub use {
-    connect::{ConnectOpts, DatasetConn},
+    backend::BackendOpts,
+    connect::ConnectOpts,
+    dataset::{split_glob, DatasetConn, FileOpts, IpcFormat, ReadOpts},
     describe::DescribeOpts,
+    disconnect::DisconnectOpts,
+    format::{FormatOpts, OutputFormat},
     head::HeadOpts,
     list::ListOpts,
+    parquet_meta::ParquetMetaOpts,
+    refresh::RefreshOpts,
+    rename::RenameOpts,
+    save::{SaveFile, SaveFormat, SaveOpts},
     schema::SchemaOpts,
+    session::SessionOpts,
+    set::SetOpts,
+    show::ShowOpts,
     sql::SqlOpts,
 };
 
-use clap::Parser;
+use std::sync::OnceLock;
+
+use clap::{CommandFactory, Parser};
 use enum_dispatch::enum_dispatch;
+use regex::Regex;
+
+/// Parse the input into a command. Input not starting with a command name, or
+/// terminated with `;`, is taken as SQL.
+pub(crate) fn parse_input(input: &str) -> Result<ReplCommand, clap::Error> {
+    let input = input.trim();
+    if is_command(input) {
+        let args = std::iter::once("taotie".to_string()).chain(split_line(input));
+        ReplCommand::try_parse_from(args)
+    } else {
+        let sql = input.trim_end_matches(';').trim_end();
+        Ok(SqlOpts::new(sql.to_string()).into())
+    }
+}
+
+/// Whether the input can be run as is: commands take a single line,
+/// while SQL continues until it is terminated with `;`.
+pub fn is_complete(input: &str) -> bool {
+    let input = input.trim();
+    input.is_empty() || is_command(input) || input.ends_with(';')
+}
+
+/// Split a script into inputs, following the same rules as the prompt.
+/// Blank lines and `#` or `--` comments between inputs are skipped.
+pub fn split_script(script: &str) -> Vec<String> {
+    let mut inputs = vec![];
+    let mut buf = String::new();
+    for line in script.lines() {
+        let trimmed = line.trim();
+        let comment = trimmed.starts_with('#') || trimmed.starts_with("--");
+        if buf.is_empty() && (trimmed.is_empty() || comment) {
+            continue;
+        }
+        buf.push_str(line);
+        buf.push('\n');
+        if is_complete(&buf) {
+            inputs.push(std::mem::take(&mut buf));
+        }
+    }
+    // an unterminated statement at the end of the script is still run
+    if !buf.trim().is_empty() {
+        inputs.push(buf);
+    }
+    inputs
+}
+
+/// The names of all commands, including the `help` generated by clap.
+pub fn command_names() -> Vec<String> {
+    let mut names = ReplCommand::command()
+        .get_subcommands()
+        .map(|c| c.get_name().to_string())
+        .collect::<Vec<_>>();
+    names.push("help".to_string());
+    names
+}
 
-type ReplResult = Result<Option<String>, reedline_repl_rs::Error>;
+/// Whether the input is a command rather than SQL. Commands start with a command name,
+/// and are never terminated with `;`, so SQL starting with the same word as a command,
+/// like `show tables;` or `describe t;`, is still run as SQL.
+fn is_command(input: &str) -> bool {
+    static NAMES: OnceLock<Vec<String>> = OnceLock::new();
+    let input = input.trim();
+    let first = input.split_whitespace().next().unwrap_or_default();
+    !input.ends_with(';') && NAMES.get_or_init(command_names).iter().any(|n| n == first)
+}
+
+/// Split a command line into arguments, double quoted arguments may contain spaces,
+/// and `\"` or `\\` for a quote or a backslash.
+fn split_line(line: &str) -> Vec<String> {
+    static ARG_RE: OnceLock<Regex> = OnceLock::new();
+    let re = ARG_RE.get_or_init(|| Regex::new(r#""((?:[^"\\\n]|\\.)*)"|\S+"#).unwrap());
+    re.captures_iter(line)
+        .map(|c| match c.get(1) {
+            Some(quoted) => unescape(quoted.as_str()),
+            None => c[0].replace('"', ""),
+        })
+        .collect()
+}
+
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('\\', Some(&next @ ('"' | '\\'))) => {
+                out.push(next);
+                chars.next();
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Quote an argument so [`split_line`] reads it back as is.
+pub(crate) fn quote_arg(arg: &str) -> String {
+    format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+}
 
 #[derive(Debug, Parser)]
 #[enum_dispatch(CmdExector)]
@@ -30,7 +147,22 @@ pub enum ReplCommand {
         about = "Connect to a database and register it to Taotie"
     )]
     Connect(ConnectOpts),
-    #[command(name = "list", about = "List all registered datasets")]
+    #[command(
+        name = "disconnect",
+        about = "Remove a dataset, along with the tables registered for it"
+    )]
+    Disconnect(DisconnectOpts),
+    #[command(name = "rename", about = "Rename a dataset")]
+    Rename(RenameOpts),
+    #[command(
+        name = "refresh",
+        about = "Connect a dataset again, picking up the changes of its files"
+    )]
+    Refresh(RefreshOpts),
+    #[command(
+        name = "list",
+        about = "List all registered datasets, with their source, format and time of connection"
+    )]
     List(ListOpts),
     #[command(name = "schema", about = "Show the schema of a dataset")]
     Schema(SchemaOpts),
@@ -38,6 +170,115 @@ pub enum ReplCommand {
     Describe(DescribeOpts),
     #[command(name = "head", about = "Show the first few rows of a dataset")]
     Head(HeadOpts),
+    #[command(
+        name = "parquet-meta",
+        about = "Show the footer of parquet files: schema, metadata, row groups and column chunks"
+    )]
+    ParquetMeta(ParquetMetaOpts),
     #[command(name = "sql", about = "Query a dataset using given SQL")]
     Sql(SqlOpts),
+    #[command(
+        name = "save",
+        about = "Save a dataset or the result of a SQL query to a file"
+    )]
+    Save(SaveOpts),
+    #[command(
+        name = "backend",
+        about = "Switch the query engine, the connected datasets are registered again"
+    )]
+    Backend(BackendOpts),
+    #[command(name = "format", about = "Set the output format of results")]
+    Format(FormatOpts),
+    #[command(
+        name = "session",
+        about = "Save the connected datasets to a session file, or connect those of a saved session"
+    )]
+    Session(SessionOpts),
+    #[command(name = "set", about = "Set a DataFusion session option")]
+    Set(SetOpts),
+    #[command(
+        name = "show",
+        about = "Show the DataFusion session options, or those matching a key"
+    )]
+    Show(ShowOpts),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sql(input: &str) -> Option<String> {
+        match parse_input(input).unwrap() {
+            ReplCommand::Sql(opts) => Some(opts.query),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn sql_starting_with_a_command_name_should_be_sql() {
+        let cases = [
+            ("show tables;", "show tables"),
+            (
+                "set datafusion.execution.batch_size = 1;",
+                "set datafusion.execution.batch_size = 1",
+            ),
+            ("describe t;", "describe t"),
+            ("list ;", "list"),
+            ("SELECT 1;", "SELECT 1"),
+            (
+                "select *\nfrom t\nwhere a = 1 ;\n",
+                "select *\nfrom t\nwhere a = 1",
+            ),
+        ];
+        for (input, query) in cases {
+            assert_eq!(sql(input).as_deref(), Some(query), "input {:?}", input);
+        }
+    }
+
+    #[test]
+    fn commands_should_be_parsed_as_commands() {
+        let cases = [
+            ("show", "Show"),
+            ("show datafusion.execution", "Show"),
+            ("set datafusion.execution.batch_size 1", "Set"),
+            ("describe t --top", "Describe"),
+            ("list", "List"),
+            ("sql \"select 1;\"", "Sql"),
+        ];
+        for (input, variant) in cases {
+            let cmd = parse_input(input).unwrap();
+            assert!(
+                format!("{:?}", cmd).starts_with(variant),
+                "{:?} parsed as {:?}",
+                input,
+                cmd
+            );
+        }
+        assert_eq!(sql("sql \"select 1;\"").as_deref(), Some("select 1;"));
+    }
+
+    #[test]
+    fn is_complete_should_wait_for_terminated_sql() {
+        assert!(is_complete(""));
+        assert!(is_complete("list"));
+        assert!(is_complete("show tables;"));
+        assert!(is_complete("select 1\n;"));
+        assert!(!is_complete("select 1"));
+        assert!(!is_complete("select *\nfrom t"));
+    }
+
+    #[test]
+    fn split_script_should_split_commands_and_sql() {
+        let script =
+            "# setup\nconnect a.csv -n a\n\nshow tables;\nselect *\n-- inline\nfrom a;\nlist\n";
+        assert_eq!(
+            split_script(script),
+            [
+                "connect a.csv -n a\n",
+                "show tables;\n",
+                "select *\n-- inline\nfrom a;\n",
+                "list\n"
+            ]
+        );
+    }
 }
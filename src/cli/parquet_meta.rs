@@ -0,0 +1,19 @@
+use clap::Parser;
+
+use crate::{BackendRegistry, CmdExector, ReplDisplay};
+
+#[derive(Debug, Parser)]
+pub struct ParquetMetaOpts {
+    #[arg(help = "The name of a parquet dataset, or the path of parquet files")]
+    pub target: String,
+}
+
+impl CmdExector for ParquetMetaOpts {
+    async fn execute(self, backend: &mut BackendRegistry) -> anyhow::Result<String> {
+        let mut out = vec![];
+        for meta in backend.parquet_meta(&self.target)? {
+            out.push(meta.display(backend.format()).await?);
+        }
+        Ok(out.join("\n\n"))
+    }
+}
@@ -0,0 +1,16 @@
+use clap::Parser;
+
+use crate::{BackendRegistry, CmdExector};
+
+#[derive(Debug, Parser)]
+pub struct RefreshOpts {
+    #[arg(help = "The name of the dataset")]
+    pub name: String,
+}
+
+impl CmdExector for RefreshOpts {
+    async fn execute(self, backend: &mut BackendRegistry) -> anyhow::Result<String> {
+        backend.refresh(&self.name).await?;
+        Ok(format!("Refreshed dataset: {}", self.name))
+    }
+}
@@ -0,0 +1,22 @@
+use clap::Parser;
+
+use crate::{Backend, BackendRegistry, CmdExector};
+
+#[derive(Debug, Parser)]
+pub struct RenameOpts {
+    #[arg(help = "The name of the dataset")]
+    pub name: String,
+
+    #[arg(help = "The new name of the dataset")]
+    pub new_name: String,
+}
+
+impl CmdExector for RenameOpts {
+    async fn execute(self, backend: &mut BackendRegistry) -> anyhow::Result<String> {
+        backend.rename(&self.name, &self.new_name).await?;
+        Ok(format!(
+            "Renamed dataset {} to {}",
+            self.name, self.new_name
+        ))
+    }
+}
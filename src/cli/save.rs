@@ -0,0 +1,112 @@
+use std::path::Path;
+
+use clap::{ArgGroup, Parser};
+use datafusion::datasource::file_format::file_compression_type::FileCompressionType;
+
+use crate::{Backend, BackendRegistry, CmdExector};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveFormat {
+    Parquet,
+    Csv,
+    NdJson,
+    Arrow,
+    ArrowStream,
+}
+
+#[derive(Debug, Clone)]
+pub struct SaveFile {
+    pub path: String,
+    pub format: SaveFormat,
+    pub compression: FileCompressionType,
+}
+
+#[derive(Debug, Parser)]
+#[command(group(ArgGroup::new("source").required(true).args(["name", "query"])))]
+pub struct SaveOpts {
+    #[arg(help = "The name of the dataset to save")]
+    pub name: Option<String>,
+
+    #[arg(short, long, help = "The SQL query whose result is saved")]
+    pub query: Option<String>,
+
+    #[arg(short, long, value_parser = verify_save_path, help = "The file to write, the format is decided by its extension(support: parquet, csv, json, arrow, arrows for arrow streams, with .gz, .bz2, .xz or .zst for csv and json)")]
+    pub output: SaveFile,
+}
+
+impl SaveFile {
+    /// The same file under a hidden name in the target directory, written first
+    /// and renamed to the target once complete.
+    pub fn staged(&self) -> SaveFile {
+        let path = Path::new(&self.path);
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        SaveFile {
+            path: path
+                .with_file_name(format!(".{}.taotie-tmp", name))
+                .to_string_lossy()
+                .into_owned(),
+            ..self.clone()
+        }
+    }
+}
+
+impl SaveOpts {
+    pub fn new(name: Option<String>, query: Option<String>, output: SaveFile) -> Self {
+        Self {
+            name,
+            query,
+            output,
+        }
+    }
+}
+
+impl CmdExector for SaveOpts {
+    async fn execute(self, backend: &mut BackendRegistry) -> anyhow::Result<String> {
+        let sql = match (&self.query, &self.name) {
+            (Some(query), _) => query.to_string(),
+            (None, Some(name)) => format!("SELECT * FROM {}", name),
+            (None, None) => anyhow::bail!("Either a dataset name or a query is required"),
+        };
+        let rows = backend.save(&sql, &self.output).await?;
+        Ok(format!("Saved {} rows to {}", rows, self.output.path))
+    }
+}
+
+fn verify_save_path(s: &str) -> Result<SaveFile, String> {
+    // process .csv, .csv.gz, .csv.bz2, .csv.xz, .csv.zst
+    let mut exts = s.rsplit('.');
+    let ext1 = exts.next().unwrap_or_default();
+    let (ext, compression) = match ext1 {
+        "gz" => (exts.next(), FileCompressionType::GZIP),
+        "bz2" => (exts.next(), FileCompressionType::BZIP2),
+        "xz" => (exts.next(), FileCompressionType::XZ),
+        "zst" | "zstd" => (exts.next(), FileCompressionType::ZSTD),
+        _ => (Some(ext1), FileCompressionType::UNCOMPRESSED),
+    };
+
+    let format = match ext {
+        Some("csv") => SaveFormat::Csv,
+        Some("json" | "jsonl" | "ndjson") => SaveFormat::NdJson,
+        Some("parquet") => SaveFormat::Parquet,
+        Some("arrow" | "ipc" | "feather") => SaveFormat::Arrow,
+        Some("arrows") => SaveFormat::ArrowStream,
+        _ => return Err(format!("Invalid output file extension: {}", s)),
+    };
+    if compression.is_compressed()
+        && matches!(
+            format,
+            SaveFormat::Parquet | SaveFormat::Arrow | SaveFormat::ArrowStream
+        )
+    {
+        return Err(format!(
+            "Compression suffix is only supported for csv and json: {}",
+            s
+        ));
+    }
+
+    Ok(SaveFile {
+        path: s.to_string(),
+        format,
+        compression,
+    })
+}
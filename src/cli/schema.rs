@@ -1,8 +1,6 @@
-use clap::{ArgMatches, Parser};
+use clap::Parser;
 
-use crate::{CmdExector, ReplContext, ReplDisplay, ReplMsg};
-
-use super::ReplResult;
+use crate::{Backend, BackendRegistry, CmdExector, ReplDisplay};
 
 #[derive(Debug, Parser)]
 pub struct SchemaOpts {
@@ -10,16 +8,6 @@ pub struct SchemaOpts {
     pub name: String,
 }
 
-pub fn schema(args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
-    let name = args
-        .get_one::<String>("name")
-        .expect("expect name")
-        .to_string();
-
-    let (msg, rx) = ReplMsg::new(SchemaOpts::new(name));
-    Ok(ctx.send(msg, rx))
-}
-
 impl SchemaOpts {
     pub fn new(name: String) -> Self {
         Self { name }
@@ -27,8 +15,8 @@ impl SchemaOpts {
 }
 
 impl CmdExector for SchemaOpts {
-    async fn execute<T: crate::Backend>(self, backend: &mut T) -> anyhow::Result<String> {
+    async fn execute(self, backend: &mut BackendRegistry) -> anyhow::Result<String> {
         let df = backend.schema(&self.name).await?;
-        df.display().await
+        Ok(df.display(backend.format()).await?)
     }
 }
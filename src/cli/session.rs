@@ -0,0 +1,56 @@
+use std::path::PathBuf;
+
+use clap::{Parser, ValueEnum};
+
+use crate::{backend::default_session_file, BackendRegistry, CmdExector};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SessionAction {
+    /// Write the connected datasets to the session file.
+    Save,
+    /// Connect the datasets of the session file.
+    Load,
+}
+
+#[derive(Debug, Parser)]
+pub struct SessionOpts {
+    #[arg(value_enum, help = "Save or load the session")]
+    pub action: SessionAction,
+
+    #[arg(help = "The session file, ~/.taotie_session if omitted")]
+    pub path: Option<PathBuf>,
+}
+
+impl SessionOpts {
+    pub fn new(action: SessionAction, path: Option<PathBuf>) -> Self {
+        Self { action, path }
+    }
+}
+
+impl CmdExector for SessionOpts {
+    async fn execute(self, backend: &mut BackendRegistry) -> anyhow::Result<String> {
+        let path = self.path.unwrap_or_else(default_session_file);
+        match self.action {
+            SessionAction::Save => {
+                let count = backend.save_session(&path)?;
+                Ok(format!(
+                    "Saved {} datasets to session: {}",
+                    count,
+                    path.display()
+                ))
+            }
+            SessionAction::Load => {
+                let (connected, failed) = backend.load_session(&path).await?;
+                let mut ret = format!(
+                    "Restored {} datasets from session: {}",
+                    connected.len(),
+                    path.display()
+                );
+                for (name, e) in failed {
+                    ret.push_str(&format!("\nFailed to restore dataset {}: {}", name, e));
+                }
+                Ok(ret)
+            }
+        }
+    }
+}
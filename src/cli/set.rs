@@ -0,0 +1,25 @@
+use clap::Parser;
+
+use crate::{Backend, BackendRegistry, CmdExector};
+
+#[derive(Debug, Parser)]
+pub struct SetOpts {
+    #[arg(help = "The option, e.g. datafusion.execution.batch_size or just batch_size")]
+    pub key: String,
+
+    #[arg(help = "The value of the option")]
+    pub value: String,
+}
+
+impl SetOpts {
+    pub fn new(key: String, value: String) -> Self {
+        Self { key, value }
+    }
+}
+
+impl CmdExector for SetOpts {
+    async fn execute(self, backend: &mut BackendRegistry) -> anyhow::Result<String> {
+        let key = backend.set_option(&self.key, &self.value).await?;
+        Ok(format!("Set {} = {}", key, self.value))
+    }
+}
@@ -0,0 +1,24 @@
+use clap::Parser;
+
+use crate::{Backend, BackendRegistry, CmdExector, ReplDisplay};
+
+#[derive(Debug, Parser)]
+pub struct ShowOpts {
+    #[arg(
+        help = "The option or namespace to show, e.g. datafusion.execution.parquet, all if omitted"
+    )]
+    pub key: Option<String>,
+}
+
+impl ShowOpts {
+    pub fn new(key: Option<String>) -> Self {
+        Self { key }
+    }
+}
+
+impl CmdExector for ShowOpts {
+    async fn execute(self, backend: &mut BackendRegistry) -> anyhow::Result<String> {
+        let options = backend.show_options(self.key.as_deref()).await?;
+        Ok(options.display(backend.format()).await?)
+    }
+}
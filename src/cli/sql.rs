@@ -1,8 +1,6 @@
-use clap::{ArgMatches, Parser};
+use clap::Parser;
 
-use crate::{CmdExector, ReplContext, ReplDisplay, ReplMsg};
-
-use super::ReplResult;
+use crate::{Backend, BackendRegistry, CmdExector, ReplDisplay};
 
 #[derive(Debug, Parser)]
 pub struct SqlOpts {
@@ -10,16 +8,6 @@ pub struct SqlOpts {
     pub query: String,
 }
 
-pub fn sql(args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
-    let query = args
-        .get_one::<String>("query")
-        .expect("expect sql query")
-        .to_string();
-
-    let (msg, rx) = ReplMsg::new(SqlOpts::new(query));
-    Ok(ctx.send(msg, rx))
-}
-
 impl SqlOpts {
     pub fn new(query: String) -> Self {
         Self { query }
@@ -27,8 +15,8 @@ impl SqlOpts {
 }
 
 impl CmdExector for SqlOpts {
-    async fn execute<T: crate::Backend>(self, backend: &mut T) -> anyhow::Result<String> {
+    async fn execute(self, backend: &mut BackendRegistry) -> anyhow::Result<String> {
         let df = backend.sql(&self.query).await?;
-        df.display().await
+        Ok(df.display(backend.format()).await?)
     }
 }
@@ -0,0 +1,93 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use serde::Deserialize;
+
+use crate::{Engine, OutputFormat};
+
+pub const DEFAULT_HEAD_SIZE: usize = 5;
+
+/// Settings read from `~/.config/taotie/config.toml`, flags given on the command line take precedence.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub repl: ReplConfig,
+    pub datafusion: DataFusionConfig,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReplConfig {
+    pub engine: Engine,
+    pub format: OutputFormat,
+    /// The number of rows `head` shows without `-n`.
+    pub head_size: usize,
+    pub history_file: PathBuf,
+    pub history_size: usize,
+    pub banner: String,
+    /// Scripts run before the first command, e.g. to connect the usual datasets.
+    pub startup: Vec<PathBuf>,
+}
+
+/// Settings of the DataFusion session, its own defaults are used if omitted.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DataFusionConfig {
+    pub batch_size: Option<usize>,
+    pub target_partitions: Option<usize>,
+}
+
+impl Config {
+    pub fn default_path() -> PathBuf {
+        home_dir()
+            .join(".config")
+            .join("taotie")
+            .join("config.toml")
+    }
+
+    /// Read the config file, the defaults are used if it does not exist.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e).context(format!("Failed to read {}", path.display())),
+        };
+        let mut config: Self = toml::from_str(&content)
+            .with_context(|| format!("Invalid config file {}", path.display()))?;
+
+        config.repl.history_file = expand_home(&config.repl.history_file);
+        for script in &mut config.repl.startup {
+            *script = expand_home(script);
+        }
+        Ok(config)
+    }
+}
+
+impl Default for ReplConfig {
+    fn default() -> Self {
+        Self {
+            engine: Engine::default(),
+            format: OutputFormat::default(),
+            head_size: DEFAULT_HEAD_SIZE,
+            history_file: home_dir().join(".taotie_history"),
+            history_size: 1024,
+            banner: "Welcome to Taotie REPL!\n".to_string(),
+            startup: vec![],
+        }
+    }
+}
+
+fn home_dir() -> PathBuf {
+    dirs::home_dir().expect("expect home dir")
+}
+
+/// Paths in the config file may start with `~/`.
+fn expand_home(path: &Path) -> PathBuf {
+    match path.strip_prefix("~") {
+        Ok(rest) => home_dir().join(rest),
+        Err(_) => path.to_path_buf(),
+    }
+}
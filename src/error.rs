@@ -0,0 +1,122 @@
+use std::path::PathBuf;
+
+use arrow::error::ArrowError;
+use datafusion::error::DataFusionError;
+use parquet::errors::ParquetError;
+use polars::error::PolarsError;
+use thiserror::Error;
+
+/// Errors returned by the REPL callbacks, printed at the prompt.
+#[derive(Debug, Error)]
+pub enum ReplError {
+    #[error(transparent)]
+    Parse(#[from] clap::Error),
+
+    #[error("Error: {0:#}")]
+    Command(anyhow::Error),
+
+    #[error("Error: the backend has stopped")]
+    BackendStopped,
+}
+
+/// Errors returned by a `Backend`.
+#[derive(Debug, Error)]
+pub enum BackendError {
+    /// Database connectors attach their own context to the error.
+    #[error("{0:#}")]
+    Connector(anyhow::Error),
+
+    #[error("Dataset not found: {0}")]
+    DatasetNotFound(String),
+
+    #[error("Dataset already connected: {0}, `refresh` it or `disconnect` it first")]
+    DatasetExists(String),
+
+    #[error("Not supported: {0}")]
+    Unsupported(String),
+
+    #[error("File not found: {0}")]
+    FileNotFound(String),
+
+    #[error("Invalid connection string {conn}: {reason}")]
+    InvalidConnection { conn: String, reason: String },
+
+    #[error("Unknown option: {key}{}", did_you_mean(.suggestions))]
+    UnknownOption {
+        key: String,
+        suggestions: Vec<String>,
+    },
+
+    #[error("Invalid value {value} for {key}: {reason}")]
+    InvalidOptionValue {
+        key: String,
+        value: String,
+        reason: Box<DataFusionError>,
+    },
+
+    #[error("Session file {}: {1}", .0.display())]
+    Session(PathBuf, std::io::Error),
+
+    #[error("Invalid session file {}, `{input}`: {reason}", path.display())]
+    InvalidSession {
+        path: PathBuf,
+        input: String,
+        reason: String,
+    },
+
+    #[error("Sheet not found: {0}")]
+    SheetNotFound(String),
+
+    #[error("Invalid sheet {sheet}: {reason}")]
+    InvalidSheet { sheet: String, reason: String },
+
+    #[error(transparent)]
+    Excel(#[from] calamine::Error),
+
+    #[error(transparent)]
+    DataFusion(#[from] DataFusionError),
+
+    #[error(transparent)]
+    Polars(#[from] PolarsError),
+
+    #[error(transparent)]
+    Arrow(#[from] ArrowError),
+
+    #[error(transparent)]
+    Parquet(#[from] ParquetError),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Describe(#[from] DescribeError),
+}
+
+fn did_you_mean(suggestions: &[String]) -> String {
+    const MAX_SUGGESTIONS: usize = 5;
+    match suggestions.len() {
+        0 => String::new(),
+        n if n > MAX_SUGGESTIONS => format!(
+            ", did you mean one of: {}, ...",
+            suggestions[..MAX_SUGGESTIONS].join(", ")
+        ),
+        _ => format!(", did you mean one of: {}", suggestions.join(", ")),
+    }
+}
+
+/// Errors from computing the statistics of `describe`.
+#[derive(Debug, Error)]
+pub enum DescribeError {
+    #[error("Failed to compute statistics: {0}")]
+    Aggregate(#[source] DataFusionError),
+
+    #[error("Failed to read {method} of column {column}: {source}")]
+    Statistic {
+        method: String,
+        column: String,
+        source: ArrowError,
+    },
+
+    #[error("Failed to build describe result: {0}")]
+    Build(#[source] ArrowError),
+}
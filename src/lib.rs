@@ -1,90 +1,174 @@
 mod backend;
 mod cli;
-
-use backend::DataFusionBackend;
-pub use cli::ReplCommand;
-use cli::{ConnectOpts, DescribeOpts, HeadOpts, ListOpts, SchemaOpts, SqlOpts};
+mod config;
+mod error;
+mod repl;
+
+pub use backend::{default_session_file, Catalog, Engine, SharedCatalog};
+use backend::{BackendRegistry, DescribeMethod};
+use clap::error::ErrorKind;
+pub use cli::{is_complete, split_script, OutputFormat, ReplCommand};
+use cli::{
+    BackendOpts, ConnectOpts, DescribeOpts, DisconnectOpts, FormatOpts, HeadOpts, ListOpts,
+    ParquetMetaOpts, RefreshOpts, RenameOpts, SaveFile, SaveOpts, SchemaOpts, SessionOpts, SetOpts,
+    ShowOpts, SqlOpts,
+};
+pub use config::{Config, DataFusionConfig};
 use enum_dispatch::enum_dispatch;
+pub use error::{BackendError, ReplError};
+pub use repl::Repl;
 use tokio::runtime::Runtime;
 
-use std::{ops::Deref, thread};
+use std::{ops::Deref, path::PathBuf, thread};
 
 use crossbeam_channel as mpsc;
-use reedline_repl_rs::CallBackMap;
 
 #[enum_dispatch]
 trait CmdExector {
-    async fn execute<T: Backend>(self, backend: &mut T) -> anyhow::Result<String>;
+    async fn execute(self, backend: &mut BackendRegistry) -> anyhow::Result<String>;
 }
 
 trait Backend {
-    async fn connect(&mut self, opts: &ConnectOpts) -> anyhow::Result<()>;
-    async fn list(&self) -> anyhow::Result<impl ReplDisplay>;
-    async fn schema(&self, name: &str) -> anyhow::Result<impl ReplDisplay>;
-    async fn describe(&self, name: &str) -> anyhow::Result<impl ReplDisplay>;
-    async fn head(&self, name: &str, size: usize) -> anyhow::Result<impl ReplDisplay>;
-    async fn sql(&self, sql: &str) -> anyhow::Result<impl ReplDisplay>;
+    async fn connect(&mut self, opts: &ConnectOpts) -> Result<(), BackendError>;
+    /// Remove a dataset, with the tables of a database registered under its name.
+    async fn disconnect(&mut self, name: &str) -> Result<(), BackendError>;
+    async fn rename(&mut self, from: &str, to: &str) -> Result<(), BackendError>;
+    /// The names of the registered tables.
+    async fn tables(&self) -> Result<Vec<String>, BackendError>;
+    async fn schema(&self, name: &str) -> Result<impl ReplDisplay, BackendError>;
+    async fn describe(
+        &self,
+        name: &str,
+        methods: &[DescribeMethod],
+    ) -> Result<impl ReplDisplay, BackendError>;
+    async fn head(&self, name: &str, size: usize) -> Result<impl ReplDisplay, BackendError>;
+    async fn sql(&self, sql: &str) -> Result<impl ReplDisplay, BackendError>;
+    async fn save(&self, sql: &str, file: &SaveFile) -> Result<usize, BackendError>;
+    async fn catalog(&self) -> Result<Catalog, BackendError>;
+    /// Set a session option of the engine, returns its full key.
+    async fn set_option(&mut self, key: &str, value: &str) -> Result<String, BackendError>;
+    async fn show_options(&self, key: Option<&str>) -> Result<impl ReplDisplay, BackendError>;
 }
 
 trait ReplDisplay {
-    async fn display(self) -> anyhow::Result<String>;
+    async fn display(self, format: OutputFormat) -> Result<String, BackendError>;
+}
+
+/// Options of a repl session.
+#[derive(Debug, Clone)]
+pub struct ReplOptions {
+    pub engine: Engine,
+    pub format: OutputFormat,
+    /// Record every connected dataset in this session file.
+    pub session: Option<PathBuf>,
+    /// The number of rows `head` shows without `-n`.
+    pub head_size: usize,
+    pub datafusion: DataFusionConfig,
 }
 
 pub struct ReplContext {
     pub tx: mpsc::Sender<ReplMsg>,
+    catalog: SharedCatalog,
 }
 
 pub struct ReplMsg {
     cmd: ReplCommand,
-    tx: oneshot::Sender<String>,
-}
-
-pub type ReplCallbacks = CallBackMap<ReplContext, reedline_repl_rs::Error>;
-
-pub fn get_callbacks() -> ReplCallbacks {
-    let mut callbacks = CallBackMap::new();
-    callbacks.insert("connect".to_string(), cli::connect);
-    callbacks.insert("list".to_string(), cli::list);
-    callbacks.insert("schema".to_string(), cli::schema);
-    callbacks.insert("describe".to_string(), cli::describe);
-    callbacks.insert("head".to_string(), cli::head);
-    callbacks.insert("sql".to_string(), cli::sql);
-    callbacks
+    tx: oneshot::Sender<anyhow::Result<String>>,
 }
 
 impl ReplContext {
     pub fn new() -> Self {
+        Self::with_options(ReplOptions::default())
+    }
+
+    pub fn with_options(opts: ReplOptions) -> Self {
         let (tx, rx) = mpsc::unbounded::<ReplMsg>();
 
-        let rt = Runtime::new().expect("Failed to create runtime");
-
-        let mut backend = DataFusionBackend::new();
-
-        thread::Builder::new()
-            .name("ReplBackend".to_string())
-            .spawn(move || {
-                while let Ok(msg) = rx.recv() {
-                    if let Err(e) = rt.block_on(async {
-                        let ret = msg.cmd.execute(&mut backend).await?;
-                        msg.tx.send(ret)?;
-                        Ok::<_, anyhow::Error>(())
-                    }) {
-                        eprintln!("Failed to execute command: {}", e);
-                    }
-                }
-            })
-            .unwrap();
-
-        Self { tx }
+        let catalog = SharedCatalog::default();
+        spawn_backend(BackendRegistry::new(opts), rx, catalog.clone());
+
+        Self { tx, catalog }
+    }
+
+    /// The datasets, columns and functions of the backend, kept up to date after every command.
+    pub fn catalog(&self) -> SharedCatalog {
+        self.catalog.clone()
     }
 
-    pub fn send(&self, msg: ReplMsg, rx: oneshot::Receiver<String>) -> Option<String> {
+    pub fn send(
+        &self,
+        msg: ReplMsg,
+        rx: oneshot::Receiver<anyhow::Result<String>>,
+    ) -> Result<Option<String>, ReplError> {
         if let Err(e) = self.tx.send(msg) {
             eprintln!("Failed to send repl msg: {}", e);
             std::process::exit(1);
         }
 
-        rx.recv().ok()
+        match rx.recv() {
+            Ok(Ok(ret)) => Ok(Some(ret)),
+            Ok(Err(e)) => Err(ReplError::Command(e)),
+            Err(_) => Err(ReplError::BackendStopped),
+        }
+    }
+
+    /// Parse the input the same way the repl does and run it, bare SQL is run by `sql`.
+    pub fn execute(&self, input: &str) -> Result<Option<String>, ReplError> {
+        if input.trim().is_empty() {
+            return Ok(None);
+        }
+        let cmd = match cli::parse_input(input) {
+            Ok(cmd) => cmd,
+            // `help` and `--help` are reported by clap as errors
+            Err(e) if matches!(e.kind(), ErrorKind::DisplayHelp | ErrorKind::DisplayVersion) => {
+                return Ok(Some(e.render().to_string().trim_end().to_string()));
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let (msg, rx) = ReplMsg::new(cmd);
+        self.send(msg, rx)
+    }
+}
+
+/// Run the commands received from the repl on `backend` in a dedicated thread,
+/// refreshing `catalog` once they are done.
+fn spawn_backend(
+    mut backend: BackendRegistry,
+    rx: mpsc::Receiver<ReplMsg>,
+    catalog: SharedCatalog,
+) {
+    let rt = Runtime::new().expect("Failed to create runtime");
+
+    thread::Builder::new()
+        .name("ReplBackend".to_string())
+        .spawn(move || {
+            rt.block_on(refresh_catalog(&backend, &catalog));
+            while let Ok(msg) = rx.recv() {
+                let ret = rt.block_on(msg.cmd.execute(&mut backend));
+                rt.block_on(refresh_catalog(&backend, &catalog));
+                // the receiver only goes away if the repl is shutting down
+                let _ = msg.tx.send(ret);
+            }
+        })
+        .unwrap();
+}
+
+/// Completion is best effort, the previous catalog is kept if the backend fails to list it.
+async fn refresh_catalog(backend: &BackendRegistry, catalog: &SharedCatalog) {
+    if let Ok(new) = backend.catalog().await {
+        *catalog.write().unwrap() = new;
+    }
+}
+
+impl Default for ReplOptions {
+    fn default() -> Self {
+        Self {
+            engine: Engine::default(),
+            format: OutputFormat::default(),
+            session: None,
+            head_size: config::DEFAULT_HEAD_SIZE,
+            datafusion: DataFusionConfig::default(),
+        }
     }
 }
 
@@ -102,7 +186,7 @@ impl Deref for ReplContext {
 }
 
 impl ReplMsg {
-    pub fn new(cmd: impl Into<ReplCommand>) -> (Self, oneshot::Receiver<String>) {
+    pub fn new(cmd: impl Into<ReplCommand>) -> (Self, oneshot::Receiver<anyhow::Result<String>>) {
         let (tx, rx) = oneshot::channel();
         (
             Self {
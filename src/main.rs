@@ -1,22 +1,177 @@
-use reedline_repl_rs::Repl;
-use taotie::{get_callbacks, ReplCommand, ReplContext};
+use std::{
+    fs,
+    io::{self, IsTerminal, Read},
+    path::{Path, PathBuf},
+    process,
+};
 
-const HISTORY_SIZE: usize = 1024;
+use clap::Parser;
+use taotie::{
+    default_session_file, split_script, Config, Engine, OutputFormat, Repl, ReplContext,
+    ReplOptions,
+};
+
+#[derive(Debug, Parser)]
+#[command(version, about = "A simple data analysis REPL")]
+struct Args {
+    #[arg(
+        short,
+        long,
+        value_enum,
+        help = "The query engine to use, datafusion unless set in the config file"
+    )]
+    engine: Option<Engine>,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "The output format of results, table unless set in the config file"
+    )]
+    format: Option<OutputFormat>,
+
+    #[arg(
+        short,
+        long,
+        help = "Run the command and exit, could be given multiple times"
+    )]
+    command: Vec<String>,
+
+    #[arg(
+        short,
+        long,
+        conflicts_with = "command",
+        help = "Run the commands of the script file and exit, SQL may span lines until `;`"
+    )]
+    file: Option<PathBuf>,
+
+    #[arg(long, help = "Connect the datasets of the last session at startup")]
+    restore: bool,
+
+    #[arg(
+        long,
+        help = "The config file, ~/.config/taotie/config.toml if omitted"
+    )]
+    config: Option<PathBuf>,
+}
 
 fn main() -> anyhow::Result<()> {
-    let ctx = ReplContext::new();
-    let callbacks = get_callbacks();
+    let args = Args::parse();
+    let config_file = args.config.clone().unwrap_or_else(Config::default_path);
+    let config = Config::load(&config_file)?;
+    // run non-interactively if commands are given or piped over stdin
+    let interactive = args.command.is_empty() && args.file.is_none() && io::stdin().is_terminal();
+    let ctx = ReplContext::with_options(ReplOptions {
+        engine: args.engine.unwrap_or(config.repl.engine),
+        format: args.format.unwrap_or(config.repl.format),
+        // only the datasets connected at the prompt make up the last session
+        session: interactive.then(default_session_file),
+        head_size: config.repl.head_size,
+        datafusion: config.datafusion,
+    });
+
+    let session = args.restore.then(default_session_file);
+    startup(&ctx, session.as_deref(), &config.repl.startup);
+
+    if !args.command.is_empty() {
+        run_inputs(&ctx, args.command);
+    }
+    if let Some(file) = args.file {
+        let script = fs::read_to_string(&file)?;
+        run_inputs(&ctx, split_script(&script));
+    }
+    if !io::stdin().is_terminal() {
+        let mut script = String::new();
+        io::stdin().read_to_string(&mut script)?;
+        run_inputs(&ctx, split_script(&script));
+    }
+
+    Repl::new(ctx)
+        .with_history(config.repl.history_file, config.repl.history_size)
+        .with_banner(&config.repl.banner)
+        .run()
+}
+
+/// Restore the last session, then run the startup scripts. The session is read first,
+/// as the datasets connected by the scripts are recorded in it.
+fn startup(ctx: &ReplContext, session: Option<&Path>, scripts: &[PathBuf]) {
+    if let Some(session) = session {
+        run_startup(ctx, &format!("session load \"{}\"", session.display()));
+    }
+    for script in scripts {
+        match fs::read_to_string(script) {
+            Ok(script) => split_script(&script)
+                .iter()
+                .for_each(|input| run_startup(ctx, input)),
+            Err(e) => eprintln!("Failed to read startup script {}: {}", script.display(), e),
+        }
+    }
+}
+
+/// Run a command set up before those of the user, it is reported on stderr
+/// to keep stdout for the results.
+fn run_startup(ctx: &ReplContext, input: &str) {
+    match ctx.execute(input) {
+        Ok(Some(output)) => eprintln!("{}", output),
+        Ok(None) => {}
+        Err(e) => eprintln!("{}", e),
+    }
+}
+
+/// Run each input as a command.
+/// Exits after the last input, or with a non-zero code at the first failure.
+fn run_inputs(ctx: &ReplContext, inputs: Vec<String>) -> ! {
+    for input in inputs {
+        match ctx.execute(&input) {
+            Ok(Some(output)) => println!("{}", output),
+            Ok(None) => {}
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+    }
+    process::exit(0);
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
 
-    let history_file = dirs::home_dir()
-        .expect("expect home dir")
-        .join(".taotie_history");
+    use super::*;
 
-    let mut repl = Repl::new(ctx)
-        .with_history(history_file, HISTORY_SIZE)
-        .with_banner("Welcome to Taotie REPL!\n")
-        .with_derived::<ReplCommand>(callbacks);
+    #[test]
+    fn startup_should_restore_the_session_before_the_scripts() {
+        let dir = TempDir::new().unwrap();
+        let session = dir.path().join("session");
+        let script = dir.path().join("startup.sql");
+        let fixture = |name: &str| fs::canonicalize(format!("fixtures/{}", name)).unwrap();
+        fs::write(
+            &session,
+            format!(
+                "connect \"{}\" --name j\n",
+                fixture("juventus.csv").display()
+            ),
+        )
+        .unwrap();
+        fs::write(
+            &script,
+            format!(
+                "connect \"{}\" --name p\n",
+                fixture("sample.parquet").display()
+            ),
+        )
+        .unwrap();
 
-    repl.run()?;
+        let ctx = ReplContext::with_options(ReplOptions {
+            session: Some(session.clone()),
+            ..Default::default()
+        });
+        startup(&ctx, Some(&session), &[script]);
 
-    Ok(())
+        let list = ctx.execute("list").unwrap().unwrap();
+        assert!(list.contains("juventus.csv") && list.contains("sample.parquet"));
+        let recorded = fs::read_to_string(&session).unwrap();
+        assert!(recorded.contains("--name \"j\""), "{}", recorded);
+        assert!(recorded.contains("--name \"p\""), "{}", recorded);
+    }
 }
@@ -0,0 +1,224 @@
+use std::collections::HashSet;
+
+use reedline::{Completer, Span, Suggestion};
+
+use crate::{cli, SharedCatalog};
+
+/// Commands taking a dataset name as their first argument.
+const DATASET_COMMANDS: &[&str] = &[
+    "schema",
+    "describe",
+    "head",
+    "save",
+    "disconnect",
+    "rename",
+    "refresh",
+    "parquet-meta",
+];
+
+/// Commands whose arguments may contain SQL.
+const SQL_COMMANDS: &[&str] = &["sql", "save"];
+
+const SQL_KEYWORDS: &[&str] = &[
+    "SELECT",
+    "DISTINCT",
+    "FROM",
+    "WHERE",
+    "GROUP BY",
+    "HAVING",
+    "ORDER BY",
+    "LIMIT",
+    "OFFSET",
+    "AS",
+    "ON",
+    "USING",
+    "JOIN",
+    "INNER JOIN",
+    "LEFT JOIN",
+    "RIGHT JOIN",
+    "FULL JOIN",
+    "CROSS JOIN",
+    "UNION",
+    "UNION ALL",
+    "INTERSECT",
+    "EXCEPT",
+    "WITH",
+    "AND",
+    "OR",
+    "NOT",
+    "IN",
+    "EXISTS",
+    "BETWEEN",
+    "LIKE",
+    "ILIKE",
+    "IS NULL",
+    "IS NOT NULL",
+    "CASE",
+    "WHEN",
+    "THEN",
+    "ELSE",
+    "END",
+    "CAST",
+    "ASC",
+    "DESC",
+    "NULLS FIRST",
+    "NULLS LAST",
+    "OVER",
+    "PARTITION BY",
+    "CREATE TABLE",
+    "CREATE VIEW",
+    "DROP TABLE",
+    "DROP VIEW",
+    "INSERT INTO",
+    "VALUES",
+    "EXPLAIN",
+    "SHOW",
+    "DESCRIBE",
+    "TRUE",
+    "FALSE",
+    "NULL",
+];
+
+/// Completes command names, then dataset names, columns, functions and SQL keywords
+/// depending on where the cursor is.
+pub struct ReplCompleter {
+    commands: Vec<String>,
+    catalog: SharedCatalog,
+}
+
+impl ReplCompleter {
+    pub fn new(catalog: SharedCatalog) -> Self {
+        Self {
+            commands: cli::command_names(),
+            catalog,
+        }
+    }
+
+    /// The names that could follow `before`, the input up to the current word.
+    fn candidates(&self, before: &str, word: &str) -> Vec<String> {
+        let mut words = before.split_whitespace();
+        let Some(first) = words.next() else {
+            return self
+                .commands
+                .iter()
+                .cloned()
+                .chain(SQL_KEYWORDS.iter().map(|k| k.to_string()))
+                .collect();
+        };
+
+        let catalog = self.catalog.read().unwrap();
+        // input not starting with a command is SQL
+        if self.commands.iter().any(|c| c == first) {
+            if DATASET_COMMANDS.contains(&first) && words.next().is_none() {
+                return catalog.datasets.keys().cloned().collect();
+            }
+            if !SQL_COMMANDS.contains(&first) {
+                return vec![];
+            }
+        }
+
+        // `dataset.column`
+        if let Some((dataset, _)) = word.rsplit_once('.') {
+            if let Some(columns) = catalog.datasets.get(dataset) {
+                return columns
+                    .iter()
+                    .map(|c| format!("{}.{}", dataset, c))
+                    .collect();
+            }
+        }
+
+        let mut names = catalog.datasets.keys().cloned().collect::<Vec<_>>();
+        names.extend(catalog.datasets.values().flatten().cloned());
+        names.extend(catalog.functions.iter().cloned());
+        names.extend(SQL_KEYWORDS.iter().map(|k| k.to_string()));
+        names
+    }
+}
+
+impl Completer for ReplCompleter {
+    fn complete(&mut self, line: &str, pos: usize) -> Vec<Suggestion> {
+        let line = &line[..pos];
+        // `-` is left out, it is part of command names like `parquet-meta`
+        let start = line
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace() || "\"'(),;=<>+*/".contains(*c))
+            .map(|(i, c)| i + c.len_utf8())
+            .unwrap_or_default();
+        let word = line[start..].to_lowercase();
+
+        let mut names = self.candidates(&line[..start], &line[start..]);
+        names.retain(|n| n.to_lowercase().starts_with(&word));
+        let mut seen = HashSet::new();
+        names.retain(|n| seen.insert(n.clone()));
+
+        names
+            .into_iter()
+            .map(|value| Suggestion {
+                value,
+                description: None,
+                style: None,
+                extra: None,
+                span: Span::new(start, pos),
+                append_whitespace: true,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn completer() -> ReplCompleter {
+        let catalog = SharedCatalog::default();
+        {
+            let mut catalog = catalog.write().unwrap();
+            let columns = vec!["id".to_string(), "名前".to_string()];
+            catalog.datasets.insert("users".to_string(), columns);
+            catalog
+                .datasets
+                .insert("sales".to_string(), vec!["amount".to_string()]);
+            catalog.functions.push("upper".to_string());
+        }
+        ReplCompleter::new(catalog)
+    }
+
+    fn complete(line: &str) -> Vec<(String, usize)> {
+        completer()
+            .complete(line, line.len())
+            .into_iter()
+            .map(|s| (s.value, s.span.start))
+            .collect()
+    }
+
+    #[test]
+    fn commands_should_be_completed() {
+        assert_eq!(complete("disc"), [("disconnect".to_string(), 0)]);
+        assert_eq!(complete("parquet-m"), [("parquet-meta".to_string(), 0)]);
+    }
+
+    #[test]
+    fn datasets_should_follow_dataset_commands() {
+        assert_eq!(complete("head u"), [("users".to_string(), 5)]);
+        assert_eq!(complete("parquet-meta s"), [("sales".to_string(), 13)]);
+        assert!(complete("head users ").is_empty());
+    }
+
+    #[test]
+    fn columns_should_be_completed_in_sql() {
+        assert_eq!(complete("SELECT users.i"), [("users.id".to_string(), 7)]);
+        assert_eq!(complete("SELECT am"), [("amount".to_string(), 7)]);
+        assert_eq!(complete("SELECT upper(am"), [("amount".to_string(), 13)]);
+    }
+
+    #[test]
+    fn non_ascii_separators_should_start_a_word() {
+        let line = "SELECT\u{3000}名";
+        assert_eq!(complete(line), [("名前".to_string(), line.len() - 3)]);
+        assert_eq!(
+            complete("SELECT 名前,\u{3000}am"),
+            [("amount".to_string(), "SELECT 名前,\u{3000}".len())]
+        );
+    }
+}
@@ -0,0 +1,105 @@
+mod completer;
+
+use std::path::PathBuf;
+
+use reedline::{
+    default_emacs_keybindings, ColumnarMenu, DefaultHinter, DefaultPrompt, DefaultPromptSegment,
+    Emacs, ExampleHighlighter, FileBackedHistory, KeyCode, KeyModifiers, MenuBuilder, Reedline,
+    ReedlineEvent, ReedlineMenu, Signal, ValidationResult, Validator,
+};
+
+use crate::{cli, ReplContext};
+
+use self::completer::ReplCompleter;
+
+const COMPLETION_MENU: &str = "completion_menu";
+
+/// The interactive prompt, SQL may span multiple lines until it is terminated with `;`.
+pub struct Repl {
+    ctx: ReplContext,
+    history: Option<(PathBuf, usize)>,
+    banner: Option<String>,
+}
+
+impl Repl {
+    pub fn new(ctx: ReplContext) -> Self {
+        Self {
+            ctx,
+            history: None,
+            banner: None,
+        }
+    }
+
+    pub fn with_history(mut self, path: PathBuf, capacity: usize) -> Self {
+        self.history = Some((path, capacity));
+        self
+    }
+
+    pub fn with_banner(mut self, banner: &str) -> Self {
+        self.banner = Some(banner.to_string());
+        self
+    }
+
+    /// Read and run inputs until Ctrl-D, Ctrl-C only discards the current input.
+    pub fn run(self) -> anyhow::Result<()> {
+        if let Some(banner) = &self.banner {
+            println!("{}", banner);
+        }
+        let mut editor = self.line_editor()?;
+        let prompt = DefaultPrompt::new(
+            DefaultPromptSegment::Basic("taotie".to_string()),
+            DefaultPromptSegment::CurrentDateTime,
+        );
+
+        loop {
+            match editor.read_line(&prompt)? {
+                Signal::Success(input) => match self.ctx.execute(&input) {
+                    Ok(Some(output)) => println!("{}", output),
+                    Ok(None) => {}
+                    Err(e) => eprintln!("{}", e),
+                },
+                Signal::CtrlC => {}
+                Signal::CtrlD => break,
+            }
+        }
+        Ok(())
+    }
+
+    fn line_editor(&self) -> anyhow::Result<Reedline> {
+        let mut keybindings = default_emacs_keybindings();
+        keybindings.add_binding(
+            KeyModifiers::NONE,
+            KeyCode::Tab,
+            ReedlineEvent::Menu(COMPLETION_MENU.to_string()),
+        );
+        let menu = ColumnarMenu::default().with_name(COMPLETION_MENU);
+
+        let mut editor = Reedline::create()
+            .with_edit_mode(Box::new(Emacs::new(keybindings)))
+            .with_completer(Box::new(ReplCompleter::new(self.ctx.catalog())))
+            .with_menu(ReedlineMenu::EngineCompleter(Box::new(menu)))
+            .with_highlighter(Box::new(ExampleHighlighter::new(cli::command_names())))
+            .with_hinter(Box::new(DefaultHinter::default()))
+            .with_validator(Box::new(InputValidator))
+            .with_quick_completions(true);
+
+        if let Some((path, capacity)) = &self.history {
+            let history = FileBackedHistory::with_file(*capacity, path.clone())?;
+            editor = editor.with_history(Box::new(history));
+        }
+        Ok(editor)
+    }
+}
+
+/// Keep reading lines while the SQL is not terminated.
+struct InputValidator;
+
+impl Validator for InputValidator {
+    fn validate(&self, line: &str) -> ValidationResult {
+        if cli::is_complete(line) {
+            ValidationResult::Complete
+        } else {
+            ValidationResult::Incomplete
+        }
+    }
+}